@@ -0,0 +1,71 @@
+//! Compares CPU inference time of OpenCV's DNN module and ONNX Runtime on the same ONNX model.
+//!
+//! Usage: cargo bench --features ort -- <model.onnx> <classes number> <image> [network version: 7 or 8 (default)] [net size, default 640] [iterations, default 50]
+
+use road_anomaly_detection::detector::{Detector, OpenCVDetector, OrtDetector};
+
+use od_opencv::{
+    model::new_from_file,
+    model_format::{ModelFormat, ModelVersion},
+};
+use opencv::{
+    dnn::{DNN_BACKEND_OPENCV, DNN_TARGET_CPU},
+    imgcodecs::{imread, IMREAD_COLOR},
+    prelude::MatTraitConst,
+};
+
+use std::env;
+use std::time::{Duration, Instant};
+
+const CONF_THRESHOLD: f32 = 0.25;
+const NMS_THRESHOLD: f32 = 0.4;
+const WARMUP_ITERATIONS: usize = 5;
+
+fn main() {
+    // Cargo passes "--bench" to the harness-less benchmark
+    let args: Vec<String> = env::args().skip(1).filter(|arg| !arg.starts_with("--")).collect();
+    if args.len() < 3 {
+        eprintln!("Usage: cargo bench --features ort -- <model.onnx> <classes number> <image> [network version: 7 or 8] [net size] [iterations]");
+        return;
+    }
+    let weights = &args[0];
+    let classes_number: usize = args[1].parse().expect("classes number should be an integer");
+    let image = imread(&args[2], IMREAD_COLOR).expect("can't read image");
+    if image.empty() {
+        panic!("image '{}' is empty", args[2]);
+    }
+    let version = match args.get(3).map(|v| v.as_str()) {
+        Some("7") => ModelVersion::V7,
+        Some("8") | None => ModelVersion::V8,
+        Some(v) => panic!("unsupported network version '{}'. Available: 7, 8", v),
+    };
+    let net_size: i32 = args.get(4).map(|v| v.parse().expect("net size should be an integer")).unwrap_or(640);
+    let iterations: usize = args.get(5).map(|v| v.parse().expect("iterations should be an integer")).unwrap_or(50);
+
+    let model = new_from_file(weights, None, (net_size, net_size), ModelFormat::ONNX, version, DNN_BACKEND_OPENCV, DNN_TARGET_CPU, vec![]).expect("can't load model via OpenCV");
+    let mut opencv_detector = OpenCVDetector::new(model);
+    let mut ort_detector = OrtDetector::new_from_file(weights, (net_size, net_size), classes_number).expect("can't load model via ONNX Runtime");
+
+    println!("Model: '{}', input: {}x{}, iterations: {}", weights, net_size, net_size, iterations);
+    run("OpenCV DNN (CPU)", &mut opencv_detector, &image, iterations);
+    run("ONNX Runtime (CPU)", &mut ort_detector, &image, iterations);
+}
+
+fn run(name: &str, detector: &mut dyn Detector, image: &opencv::core::Mat, iterations: usize) {
+    for frame_index in 0..WARMUP_ITERATIONS {
+        detector.detect(image, frame_index as u64, CONF_THRESHOLD, NMS_THRESHOLD).expect("detection failed");
+    }
+    let mut timings: Vec<Duration> = Vec::with_capacity(iterations);
+    let mut detections = 0;
+    for frame_index in 0..iterations {
+        let started_at = Instant::now();
+        let (bboxes, _, _) = detector.detect(image, frame_index as u64, CONF_THRESHOLD, NMS_THRESHOLD).expect("detection failed");
+        timings.push(started_at.elapsed());
+        detections = bboxes.len();
+    }
+    timings.sort();
+    let total: Duration = timings.iter().sum();
+    let mean_ms = total.as_secs_f64() * 1000.0 / iterations.max(1) as f64;
+    let percentile_ms = |p: f64| timings.get(((timings.len() as f64 - 1.0) * p).round() as usize).map_or(0.0, |d| d.as_secs_f64() * 1000.0);
+    println!("{:<20} mean: {:>8.2} ms | p50: {:>8.2} ms | p95: {:>8.2} ms | detections: {}", name, mean_ms, percentile_ms(0.5), percentile_ms(0.95), detections);
+}
@@ -1,226 +1,440 @@
-use crate::{publisher::PublisherTrait, video_capture};
-use crate::video_capture::ThreadedFrame;
+use crate::{publisher::PublisherEntry, video_capture};
+use crate::video_capture::{ThreadedFrame, FrameTime, Clock, TimeSource, ReconnectPolicy, detect_time_source, reopen_video_capture};
 
-use crate::detection::process_yolo_detections;
+use crate::detector::{detect_tiled, make_tiles, Detector, DetectorBackend, DetectorOutput, DetectorError, DetectorTarget, MockDetector, OpenCVDetector, TilingAreas, TilingParams};
+#[cfg(feature = "ort")]
+use crate::detector::OrtDetector;
+use crate::detection::{process_yolo_detections, fuse_detections, DetectionInput, DetectionSources, RawDetections, DEFAULT_FUSION_IOU_THRESHOLD};
 use crate::tracker::Tracker;
-use crate::zones::Zone;
-use crate::events::EventInfo;
-use crate::publisher::redis_publisher::RedisConnection;
+use crate::zones::{Zone, ExclusionPolygon};
+use crate::events::{EventInfo, SystemEvent, SystemEventType, SystemAlert};
+use crate::outbox::Outbox;
+use crate::mjpeg::MjpegStreamer;
+use crate::rest_api::{RestApi, MonitoringServer};
+use crate::metrics::{metrics, FpsMeter};
+use crate::health::{health, Watchdog};
+use crate::background::{BackgroundModel, BackgroundParams, StaticForeground, StaticForegroundParams};
 use crate::draw::{invert_color, draw_bboxes, draw_identifiers};
 
 use crate::app::app_settings;
+use crate::app::{RuntimeSettings, SchedulingStrategy, FrameScheduler, SourceSender};
 use crate::app::app_error::AppError;
 use crate::app::app_error::AppInternalError;
 
 use opencv::{
-    core::Mat, core::{Size, Scalar, get_cuda_enabled_device_count}, highgui::imshow, highgui::named_window, highgui::resize_window, highgui::wait_key, imgproc::resize, prelude::MatTraitConst, prelude::VideoCaptureTrait, prelude::VideoCaptureTraitConst, video::{create_background_subtractor_mog2, BackgroundSubtractorMOG2Trait, BackgroundSubtractorTraitConst}, videoio::VideoCapture,
+    core::Mat, core::{Rect, Size, Scalar, get_cuda_enabled_device_count, norm2, no_array, NORM_INF}, highgui::imshow, highgui::named_window, highgui::resize_window, highgui::wait_key, imgproc::resize, prelude::MatTraitConst, prelude::VideoCaptureTrait, prelude::VideoCaptureTraitConst, videoio::VideoCapture,
+    dnn::get_available_backends,
     dnn::DNN_BACKEND_CUDA,
-    dnn::DNN_TARGET_CUDA,
+    dnn::DNN_BACKEND_INFERENCE_ENGINE,
     dnn::DNN_BACKEND_OPENCV,
     dnn::DNN_TARGET_CPU,
+    dnn::DNN_TARGET_CUDA,
+    dnn::DNN_TARGET_CUDA_FP16,
+    dnn::DNN_TARGET_MYRIAD,
+    dnn::DNN_TARGET_OPENCL,
+    dnn::DNN_TARGET_OPENCL_FP16,
+    videoio::CAP_PROP_POS_MSEC,
 };
 
 use std::thread;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use std::collections::HashSet;
 const EMPTY_FRAMES_LIMIT: u16 = 60;
+const DEFAULT_STREAM_STALL_SECONDS: u64 = 10;
+const DEFAULT_DETECTION_STALL_SECONDS: u64 = 30;
+const DEFAULT_FROZEN_FRAMES_LIMIT: u64 = 100;
+const DEFAULT_DRAIN_TIMEOUT_SECONDS: u64 = 10;
+/// OpenVINO backend as reported by `get_available_backends()` of modern OpenCV builds. It is internal constant of OpenCV,
+/// so it is not exported by bindings. Network still should be configured with `DNN_BACKEND_INFERENCE_ENGINE`
+const DNN_BACKEND_INFERENCE_ENGINE_NGRAPH: i32 = 1_000_000;
 
 use od_opencv::{
     model_format::ModelFormat,
     model_format::ModelVersion,
     model::new_from_file,
-    model::ModelTrait,
 };
 
 pub struct App {
     pub application_info: app_settings::ApplicationInfo,
-    pub input: app_settings::InputSettings,
+    /// Every video source. Single [input] is represented as the camera with identifier "default"
+    pub cameras: Vec<app_settings::CameraSettings>,
+    pub scheduling: SchedulingStrategy,
+    pub background: BackgroundParams,
+    /// Long-term background model. None if detection of static foreground is disabled
+    pub static_foreground: Option<StaticForegroundParams>,
+    /// Sliced inference. None if disabled
+    pub tiling: Option<TilingParams>,
     pub output: app_settings::OutputSettings,
+    pub mjpeg_streaming: Option<app_settings::MjpegStreamingSettings>,
     pub detection: app_settings::DetectionSettings,
     pub tracking: app_settings::TrackingSettings,
-    pub zones_settings: Option<Vec<app_settings::ZoneSettings>>,
     pub publishers: Option<app_settings::PublishersSettings>,
+    pub outbox: Option<app_settings::OutboxSettings>,
+    pub rest_api: Option<app_settings::RestApiSettings>,
+    pub monitoring: Option<app_settings::MonitoringSettings>,
+    pub watchdog: Option<app_settings::WatchdogSettings>,
+    pub shutdown: Option<app_settings::ShutdownSettings>,
+    /// Settings which could be changed via REST API while application is running
+    pub runtime_settings: Arc<RuntimeSettings>,
     pub model_format: ModelFormat,
     pub model_version: ModelVersion,
 }
 
+/// State of the detection loop for the single camera
+struct CameraPipeline {
+    settings: app_settings::CameraSettings,
+    width: f32,
+    height: f32,
+    /// Rate of the frames which reach the detection loop (could be limited by max_fps)
+    fps: f32,
+    /// Size of the images the background model and the detector work with
+    processing_size: Size,
+    scale_width: f32,
+    scale_height: f32,
+    reset_background: bool,
+    bg_subtractor: BackgroundModel,
+    static_foreground: Option<StaticForeground>,
+    foreground_mask: Mat,
+    resized_frame_for_bg: Mat,
+    tracker: Tracker,
+    global_exclusions: Vec<ExclusionPolygon>,
+    zones: Vec<Zone>,
+    target_classes: HashSet<String>,
+    /// Settings or frame size have been changed, so zones should be rebuilt
+    zones_outdated: bool,
+    window: Option<String>,
+    fps_meter: FpsMeter,
+    /// Number of frames processed by the detection loop
+    frame_index: u64,
+    /// Time of the last processed frame. Incidents which are open on shutdown are closed with it
+    last_frame_time: Option<FrameTime>,
+    /// Tiles of the processed image for sliced inference. Empty if tiling is disabled
+    tiles: Vec<Rect>,
+}
+
 impl App {
     pub fn run(&mut self) -> Result<(), AppError> {
-        let mut neural_net = prepare_neural_net(self.model_format, self.model_version, &self.detection.network_weights, self.detection.network_cfg.clone(), (self.detection.net_width, self.detection.net_height))?;
+        let started_at = Instant::now();
+        let shutdown = install_shutdown_handler()?;
+        let mut neural_net: Box<dyn Detector> = match &self.detection.mock_detections {
+            Some(mock_detections) => {
+                println!("Detections are replayed from '{}' instead of running neural network", mock_detections);
+                Box::new(MockDetector::new_from_file(mock_detections)?)
+            },
+            None => match self.detection.get_backend()? {
+                #[cfg(feature = "ort")]
+                DetectorBackend::OnnxRuntime => {
+                    println!("Model '{}' is run via ONNX Runtime (CPU)", self.detection.network_weights);
+                    Box::new(OrtDetector::new_from_file(&self.detection.network_weights, (self.detection.net_width, self.detection.net_height), self.detection.net_classes.len())?)
+                },
+                backend => prepare_neural_net(self.model_format, self.model_version, &self.detection.network_weights, self.detection.network_cfg.clone(), (self.detection.net_width, self.detection.net_height), backend, self.detection.get_target()?)?
+            }
+        };
+        health().set_model_loaded(true);
 
-        let mut video_capture = video_capture::get_video_capture(self.input.video_source.as_str(), self.input.video_source_typ.clone())?;
-        let (width, height, fps) = probe_video(&video_capture)?;
-        println!("Video probe: {{Width: {width}px | Height: {height}px | FPS: {fps}}}");
+        // Every source is opened before starting, so misconfigured camera is reported right away
+        let mut video_captures = vec![];
+        let mut pipelines = vec![];
+        for camera in self.cameras.iter() {
+            let video_capture = video_capture::get_video_capture(camera.input.video_source.as_str(), camera.input.video_source_typ.clone())?;
+            let (width, height, source_fps) = probe_video(&video_capture)?;
+            println!("Video probe [{}]: {{Width: {width}px | Height: {height}px | FPS: {source_fps}}}", camera.id);
 
-        let opened = VideoCapture::is_opened(&video_capture).map_err(AppError::from)?;
-        if !opened {
-            return Err(AppError::Internal(AppInternalError{typ: 2, txt: self.input.video_source.clone()}))
+            let opened = VideoCapture::is_opened(&video_capture).map_err(AppError::from)?;
+            if !opened {
+                return Err(AppError::Internal(AppInternalError{typ: 2, txt: camera.input.video_source.clone()}))
+            }
+            self.runtime_settings.set_frame_size(&camera.id, width, height);
+            // Skipped frames are not seen by the tracker, so it should work with the resulting rate
+            let fps = camera.get_max_fps().map(|max_fps| max_fps.min(source_fps)).unwrap_or(source_fps);
+            pipelines.push(self.prepare_pipeline(camera, width, height, fps)?);
+            video_captures.push((video_capture, source_fps));
+        }
+
+        if let Some(rest_api_settings) = &self.rest_api {
+            if rest_api_settings.enable {
+                RestApi::new(self.runtime_settings.clone()).start(&rest_api_settings.host, rest_api_settings.port)?;
+            }
+        }
+        if let Some(monitoring_settings) = &self.monitoring {
+            if monitoring_settings.enable {
+                MonitoringServer::start(&monitoring_settings.host, monitoring_settings.port)?;
+            }
         }
 
-        let (tx_capture, rx_capture): (mpsc::SyncSender<ThreadedFrame>, mpsc::Receiver<ThreadedFrame>) = mpsc::sync_channel(0);
+        // Init publishers
+        let (events_sender, events_reciever): (mpsc::SyncSender<PublishMessage>, mpsc::Receiver<PublishMessage>) = mpsc::sync_channel(0);
+        // Publishing thread reports when every event has been handled and it has exited
+        let (publishing_done_tx, publishing_done_rx) = mpsc::channel::<()>();
+        let publishers_registry = self.publishers.as_ref().map(|ps| ps.registry()).unwrap_or_default();
+        let outbox_settings = match &self.outbox {
+            Some(os) if os.enable => Some((os.clone(), os.get_overflow_policy()?)),
+            _ => None
+        };
         thread::spawn(move || {
-            let mut frames_counter: f32 = 0.0;
-            let mut total_seconds: f32 = 0.0;
-            let mut overall_seconds: f32 = 0.0;
-            let mut empty_frames_countrer: u16 = 0;
-            loop {
-                let mut read_frame = Mat::default();
-                match video_capture.read(&mut read_frame) {
-                    Ok(_) => {},
-                    Err(_) => {
-                        println!("Can't read next frame");
-                        break;
-                    }
-                };
-                if read_frame.empty() {
-                    println!("[WARNING]: Empty frame");
-                    empty_frames_countrer += 1;
-                    if empty_frames_countrer >= EMPTY_FRAMES_LIMIT {
-                        println!("Too many empty frames");
-                        break
-                    }
-                    continue;
-                }
-                frames_counter += 1.0;
-                let second_fraction = total_seconds + (frames_counter / fps);
-                if frames_counter >= fps {
-                    total_seconds += 1.0;
-                    overall_seconds += 1.0;
-                    frames_counter = 0.0;
-                }
-                let frame = ThreadedFrame{
-                    frame: read_frame,
-                    overall_seconds,
-                    current_second: second_fraction,
-                };
-                match tx_capture.send(frame) {
-                    Ok(_)=>{},
-                    Err(_err) => {
-                        // Closed channel?
-                        // println!("Error on send frame to detection thread: {}", _err)
+            let publishers = publishers_registry.build_all();
+            match outbox_settings {
+                Some((os, overflow_policy)) => {
+                    // Only publishers which have been started get deliveries. Otherwise events would wait for them forever
+                    let names: Vec<String> = publishers.iter().map(|entry| entry.name.clone()).collect();
+                    let retry_initial = Duration::from_millis(os.retry_initial_ms.unwrap_or(500));
+                    let retry_max = Duration::from_millis(os.retry_max_ms.unwrap_or(60_000));
+                    match Outbox::open(&os.path, names, os.max_events, overflow_policy, retry_initial, retry_max) {
+                        Ok(outbox) => events_processing_with_outbox(events_reciever, publishers, outbox),
+                        Err(e) => {
+                            eprintln!("Can't open outbox '{}': {}. Events will be published without persistence", os.path, e);
+                            events_processing(events_reciever, publishers);
+                        }
                     }
-                };
-            }
-            match video_capture.release() {
-                Ok(_) => {
-                    println!("Video capture has been closed successfully");
                 },
-                Err(err) => {
-                    eprintln!("Can't release video capturer due the error: {}", err);
-                }
-            };
+                None => events_processing(events_reciever, publishers)
+            }
+            let _ = publishing_done_tx.send(());
         });
 
+        let app_name = self.application_info.id.to_owned();
+        // System events (watchdog alerts, source reconnections) are published only if it is enabled explicitly
+        let system_events_sender = match &self.watchdog {
+            Some(ws) if ws.publish_events.unwrap_or(false) => Some(events_sender.clone()),
+            _ => None
+        };
 
-        let mut resized_frame = Mat::default();
-        let window = &self.output.window_name;
-        if self.output.enable {
-            named_window(window, 1)?;
-            resize_window(window, self.output.width, self.output.height)?;
+        // Comparing consecutive frames is not free, so it is done only when watchdog needs it
+        let check_frozen = self.watchdog.as_ref().map(|ws| ws.enable && ws.frozen_frames_limit.unwrap_or(DEFAULT_FROZEN_FRAMES_LIMIT) > 0).unwrap_or(false);
+        let mut scheduler: FrameScheduler<(usize, ThreadedFrame)> = FrameScheduler::new(self.scheduling);
+        println!("Frames of {} camera(s) are scheduled as {:?}", self.cameras.len(), self.scheduling);
+        let mut capture_threads = vec![];
+        for (camera_idx, (camera, (video_capture, source_fps))) in self.cameras.iter().zip(video_captures).enumerate() {
+            let clock = Clock::new(camera.input.get_time_source()?);
+            println!("Time source for lifetimes [{}]: {:?}", camera.id, clock.source());
+            let capture_params = CaptureParams {
+                camera_idx,
+                camera_id: camera.id.clone(),
+                video_source: camera.input.video_source.clone(),
+                video_source_typ: camera.input.video_source_typ.clone(),
+                fps: source_fps,
+                max_fps: camera.get_max_fps(),
+                clock,
+                check_frozen,
+                reconnect_policy: camera.input.get_reconnect_policy()?,
+                system_events_sender: system_events_sender.clone(),
+                equipment_id: app_name.clone(),
+                shutdown: shutdown.clone(),
+            };
+            let tx_capture = scheduler.add_source();
+            health().capture_started(&camera.id);
+            capture_threads.push((camera.id.clone(), thread::spawn(move || {
+                capture_frames(video_capture, capture_params, tx_capture)
+            })));
         }
-       
+
+        let mut resized_frame = Mat::default();
         let bbox_scalar: Scalar = Scalar::from((0.0, 0.0, 255.0));
         let bbox_scalar_inverse:Scalar = invert_color(&bbox_scalar);
         let id_scalar: Scalar = Scalar::from((0.0, 0.0, 255.0));
         let id_scalar_inverse: Scalar = invert_color(&id_scalar);
 
-        let mut bg_subtractor = create_background_subtractor_mog2((1.0 * fps).floor() as i32, 16.0, false)?;
-        // let mut bg_subtractor = opencv::bgsegm::create_background_subtractor_cnt(15, false, 15*60, true)?;
-        let mut foreground_mask = Mat::default();
-
-        let conf_threshold: f32 = self.detection.conf_threshold;
-        let nms_threshold: f32 = self.detection.nms_threshold;
-        let target_classes = HashSet::from_iter(self.detection.target_classes.to_owned().unwrap_or(vec![]));
+        let mut class_filters = self.detection.get_class_filters();
+        // Per-class thresholds could be lower than the global one, so the lowest of them is passed to the neural network
+        let mut conf_threshold: f32 = class_filters.min_conf_threshold();
+        let mut nms_threshold: f32 = self.detection.nms_threshold;
         let net_classes = self.detection.net_classes.to_owned();
-        let time_frac = 1.0/fps;
-        
-        let lifetime_seconds_min = self.tracking.lifetime_seconds_min as i64;
-        let lifetime_seconds_max = self.tracking.lifetime_seconds_max as i64;
-        let mut tracker: Tracker = Tracker::new(fps.floor() as usize, 0.3);
-        println!("Tracker initialized with following settings:\n\t{}", tracker);
-   
-        let app_name = self.application_info.id.to_owned();
+        let detection_input = self.detection.get_input()?;
+        let fusion_iou_threshold = self.detection.fusion_iou_threshold.unwrap_or(DEFAULT_FUSION_IOU_THRESHOLD);
+        let mut settings_version = self.runtime_settings.version();
 
-        let mut zones: Vec<Zone> = match self.zones_settings.clone() {
-            Some(d) => {
-                d.iter().map(|zone_settings| {
-                    Zone::new(zone_settings.id.clone(), zone_settings.geometry, zone_settings.color_rgb)
-                }).collect()
-            }
-            None => vec![Zone::new("whole_image".to_string(), [[5, 5], [width as i32 - 5, 5], [width as i32 - 5, height as i32 - 5], [5, height as i32 - 5]], Some([0, 0, 255]))]
+        let mut mjpeg_streamer = match &self.mjpeg_streaming {
+            Some(ms) if ms.enable => {
+                let size = ms.width.zip(ms.height);
+                Some(MjpegStreamer::start(&ms.host, ms.port, size, ms.quality.unwrap_or(80), ms.max_fps.unwrap_or(0.0))?)
+            },
+            _ => None
         };
-        
-        // Init publishers
-        let (events_sender, events_reciever): (mpsc::SyncSender<EventInfo>, mpsc::Receiver<EventInfo>) = mpsc::sync_channel(0);
-        let publishers_settings = self.publishers.to_owned();
-        thread::spawn(move || {
-            let mut publishers: Vec<Box<dyn PublisherTrait>> = vec![];
-            match publishers_settings {
-                Some(ps) => {
-                    match ps.redis {
-                        Some(redis_settings) => {
-                            let redis_conn = if redis_settings.password.is_empty() {
-                                RedisConnection::new(redis_settings.host, redis_settings.port, redis_settings.db_index, redis_settings.channel_name)
-                            } else {
-                                if redis_settings.username.is_empty() {
-                                    RedisConnection::new_with_password(redis_settings.host, redis_settings.port, redis_settings.db_index, redis_settings.channel_name, redis_settings.password)
-                                } else {
-                                    RedisConnection::new_with_username_password(redis_settings.host, redis_settings.port, redis_settings.db_index, redis_settings.channel_name, redis_settings.username, redis_settings.password)
+        let mjpeg_camera_idx = self.mjpeg_streaming.as_ref()
+            .and_then(|ms| ms.camera_id.as_ref())
+            .and_then(|camera_id| self.cameras.iter().position(|camera| &camera.id == camera_id))
+            .unwrap_or(0);
+
+        if let Some(watchdog_settings) = &self.watchdog {
+            if watchdog_settings.enable {
+                let watchdog = Watchdog::new(
+                    watchdog_settings.stream_stall_seconds.unwrap_or(DEFAULT_STREAM_STALL_SECONDS),
+                    watchdog_settings.detection_stall_seconds.unwrap_or(DEFAULT_DETECTION_STALL_SECONDS),
+                    watchdog_settings.frozen_frames_limit.unwrap_or(DEFAULT_FROZEN_FRAMES_LIMIT),
+                );
+                let system_events_sender = system_events_sender.clone();
+                let equipment_id = app_name.clone();
+                watchdog.spawn(shutdown.clone(), move |event_type, active_alert, message| {
+                    send_system_event(&system_events_sender, &equipment_id, active_alert.camera_id.as_deref(), event_type, active_alert.alert, message);
+                });
+            }
+        }
+
+        let _detection_alive = DetectionAliveGuard::new();
+        let mut frames_processed: u64 = 0;
+        let mut events_emitted: u64 = 0;
+        while let Some((camera_idx, received)) = scheduler.next(&shutdown) {
+            // Pick up settings changed via REST API
+            if self.runtime_settings.version() != settings_version {
+                settings_version = self.runtime_settings.version();
+                let updated = self.runtime_settings.snapshot();
+                let updated_cameras = updated.cameras();
+                self.detection = updated.detection;
+                self.tracking = updated.tracking;
+                class_filters = self.detection.get_class_filters();
+                conf_threshold = class_filters.min_conf_threshold();
+                nms_threshold = self.detection.nms_threshold;
+                for pipeline in pipelines.iter_mut() {
+                    if let Some(camera) = updated_cameras.iter().find(|camera| camera.id == pipeline.settings.id) {
+                        pipeline.settings = camera.clone();
+                    }
+                    pipeline.zones_outdated = true;
+                }
+                println!("Settings have been updated (version {})", settings_version);
+            }
+            let pipeline = &mut pipelines[camera_idx];
+            if let Some(processing_fps) = pipeline.fps_meter.tick() {
+                metrics().processing_fps.with_label_values(&[&pipeline.settings.id]).set(processing_fps);
+            }
+            let mut frame = received.frame.clone();
+            if received.reconnected && pipeline.reset_background {
+                pipeline.bg_subtractor = BackgroundModel::new(&self.background, pipeline.fps)?;
+                if let Some(params) = &self.static_foreground {
+                    pipeline.static_foreground = Some(StaticForeground::new(params, pipeline.fps)?);
+                }
+                println!("Background model of camera '{}' has been reset after reconnection", pipeline.settings.id);
+            }
+            // Source could change resolution after reconnection
+            let frame_size = frame.size()?;
+            if frame_size.width as f32 != pipeline.width || frame_size.height as f32 != pipeline.height {
+                println!("Frame size of camera '{}' has been changed: {}x{} -> {}x{}", pipeline.settings.id, pipeline.width, pipeline.height, frame_size.width, frame_size.height);
+                pipeline.width = frame_size.width as f32;
+                pipeline.height = frame_size.height as f32;
+                pipeline.processing_size = self.processing_size(pipeline.width, pipeline.height);
+                pipeline.scale_width = pipeline.width / pipeline.processing_size.width as f32;
+                pipeline.scale_height = pipeline.height / pipeline.processing_size.height as f32;
+                if self.tiling.is_some() {
+                    // Background model works in source resolution with tiling, so it can't be continued
+                    pipeline.bg_subtractor = BackgroundModel::new(&self.background, pipeline.fps)?;
+                    if let Some(params) = &self.static_foreground {
+                        pipeline.static_foreground = Some(StaticForeground::new(params, pipeline.fps)?);
+                    }
+                }
+                self.runtime_settings.set_frame_size(&pipeline.settings.id, pipeline.width, pipeline.height);
+                pipeline.zones_outdated = true;
+            }
+            if pipeline.zones_outdated {
+                pipeline.zones_outdated = false;
+                match self.prepare_zones(&pipeline.settings, pipeline.width, pipeline.height) {
+                    Ok((new_global_exclusions, mut new_zones)) => {
+                        for mut old_zone in std::mem::take(&mut pipeline.zones).into_iter() {
+                            match new_zones.iter_mut().find(|zone| zone.id == old_zone.id) {
+                                Some(new_zone) => new_zone.inherit_incidents(&mut old_zone),
+                                None => {
+                                    for finished_event in old_zone.close_incidents(&received.time, Some(app_name.clone()), Some(&frame)) {
+                                        send_event(&events_sender, finished_event);
+                                        events_emitted += 1;
+                                    }
                                 }
-                            };
-                            match redis_conn {
-                                Ok(conn) => publishers.push(conn),
-                                Err(e) => eprintln!("Failed to create Redis connection: {}. Ignoring Redis publisher", e),
                             }
-                        },
-                        None => {}
+                        }
+                        pipeline.global_exclusions = new_global_exclusions;
+                        pipeline.zones = new_zones;
+                        pipeline.target_classes = collect_target_classes(&pipeline.zones);
+                    },
+                    Err(err) => {
+                        // Settings are validated before being accepted, but pixel coordinates could not fit into new frame size
+                        eprintln!("Can't rebuild zones of camera '{}' due the error: {}. Previous zones are kept", pipeline.settings.id, err);
                     }
-                },
-                None => {}
+                }
+                pipeline.tiles = self.prepare_tiles(&pipeline.zones, pipeline.processing_size)?;
             }
-            events_processing(events_reciever, publishers);
-        });
-
-        let mut resized_frame_for_bg = Mat::default();
-        let scale_width = width / self.detection.net_width as f32;
-        let scale_height = height / self.detection.net_height as f32;
-
-        for received in rx_capture {
-            let mut frame = received.frame.clone();
-            // We need to resize image despite of neural network class (DNN module resizes image) since we need to speed up background subtractor
-            resize(&frame, &mut resized_frame_for_bg, Size::new(self.detection.net_width, self.detection.net_height), 1.0, 1.0, 1)?;
-            bg_subtractor.apply(&resized_frame_for_bg, &mut foreground_mask, -1.0)?;
+            // We need to resize image despite of neural network class (DNN module resizes image) since we need to speed up background subtractor.
+            // With tiling enabled the source resolution is kept, otherwise small objects would be lost
+            let bg_timer = metrics().bg_subtraction_seconds.start_timer();
+            resize(&frame, &mut pipeline.resized_frame_for_bg, pipeline.processing_size, 1.0, 1.0, 1)?;
+            pipeline.bg_subtractor.apply(&pipeline.resized_frame_for_bg, &mut pipeline.foreground_mask)?;
             let mut frame_background = Mat::default(); 
-            bg_subtractor.get_background_image(&mut frame_background)?;
-            let (nms_bboxes, nms_classes_ids, nms_confidences) = match neural_net.forward(&frame_background, conf_threshold, nms_threshold) {
-                Ok((a, b, c)) => { (a, b, c) },
-                Err(err) => {
+            pipeline.bg_subtractor.get_background_image(&mut frame_background)?;
+            if let Some(static_foreground) = pipeline.static_foreground.as_mut() {
+                static_foreground.update(&pipeline.resized_frame_for_bg, &pipeline.foreground_mask)?;
+            }
+            bg_timer.observe_duration();
+            let nn_timer = metrics().nn_forward_seconds.start_timer();
+            let background_result = match detection_input {
+                DetectionInput::Frame => Ok(RawDetections::default()),
+                _ => detect(neural_net.as_mut(), &frame_background, &pipeline.tiles, self.tiling.as_ref(), pipeline.frame_index, conf_threshold, nms_threshold).map(|(bboxes, classes_ids, confidences)| RawDetections::new(bboxes, classes_ids, confidences, DetectionSources::background())),
+            };
+            // Resized frame is used, so detections share coordinates with ones made on the background image
+            let frame_result = match detection_input {
+                DetectionInput::Background => Ok(RawDetections::default()),
+                _ => detect(neural_net.as_mut(), &pipeline.resized_frame_for_bg, &pipeline.tiles, self.tiling.as_ref(), pipeline.frame_index, conf_threshold, nms_threshold).map(|(bboxes, classes_ids, confidences)| RawDetections::new(bboxes, classes_ids, confidences, DetectionSources::frame())),
+            };
+            nn_timer.observe_duration();
+            let (background_detections, frame_detections) = match (background_result, frame_result) {
+                (Ok(background_detections), Ok(frame_detections)) => (background_detections, frame_detections),
+                (Err(err), _) | (_, Err(err)) => {
                     eprintln!("Can't process input of neural network due the error {:?}", err);
                     break;
                 }
             };
-            let mut tmp_detections = process_yolo_detections(&nms_bboxes, nms_classes_ids, nms_confidences, &net_classes, &target_classes, time_frac, scale_width, scale_height);
-            let relative_time = received.overall_seconds;
-            tracker.match_objects(&mut tmp_detections, relative_time).unwrap();
-            
-            for zone in zones.iter_mut() {
-                let registered_events = zone.process_tracker(&mut tracker, lifetime_seconds_min, lifetime_seconds_max, Some(app_name.clone()), Some(&frame))?;
+            // Detections are in coordinates of the resized frame, so they could be matched with static foreground mask directly.
+            // Only detections on the background image are gated: moving objects are never static
+            let background_detections = match &pipeline.static_foreground {
+                Some(static_foreground) => {
+                    // Tracked objects which have passed the gate are held by it, so they are not lost when long-term model absorbs them
+                    let held: Vec<(Rect, f32)> = pipeline.tracker.engine.objects.iter().filter_map(|(object_id, object)| {
+                        let static_seconds = pipeline.tracker.objects_extra.get(object_id)?.get_static_seconds()?;
+                        let bbox = object.get_bbox();
+                        Some((Rect::new(
+                            (bbox.x / pipeline.scale_width) as i32,
+                            (bbox.y / pipeline.scale_height) as i32,
+                            (bbox.width / pipeline.scale_width) as i32,
+                            (bbox.height / pipeline.scale_height) as i32,
+                        ), static_seconds))
+                    }).collect();
+                    static_foreground.filter_detections(background_detections, &held)?
+                },
+                None => background_detections
+            };
+            let raw_detections = match detection_input {
+                DetectionInput::Background => background_detections,
+                DetectionInput::Frame => frame_detections,
+                DetectionInput::Both => fuse_detections(background_detections, frame_detections, fusion_iou_threshold),
+            };
+            let time_frac = 1.0 / pipeline.fps;
+            let mut tmp_detections = process_yolo_detections(&raw_detections, &net_classes, &pipeline.target_classes, &class_filters, time_frac, pipeline.scale_width, pipeline.scale_height);
+            pipeline.tracker.match_objects(&mut tmp_detections, &received.time).unwrap();
+            metrics().active_tracks.with_label_values(&[&pipeline.settings.id]).set(pipeline.tracker.engine.objects.len() as i64);
+
+            for zone in pipeline.zones.iter_mut() {
+                let registered_events = zone.process_tracker(&mut pipeline.tracker, &received.time, Some(app_name.clone()), Some(&frame))?;
                 for new_event in registered_events {
-                    match events_sender.send(new_event) {
-                        Ok(_)=>{ },
-                        Err(_err) => {
-                            // Closed channel?
-                            eprintln!("Error on send event to postprocess thread: {}", _err)
-                        }
-                    };
+                    send_event(&events_sender, new_event);
+                    events_emitted += 1;
                 }
             }
-            if self.output.enable {
-                draw_bboxes(&mut frame, &tracker, bbox_scalar, bbox_scalar_inverse);
-                draw_identifiers(&mut frame, &tracker, id_scalar, id_scalar_inverse);
-                for zone in zones.iter() {
+            let streamed = mjpeg_streamer.is_some() && camera_idx == mjpeg_camera_idx;
+            if pipeline.window.is_some() || streamed {
+                draw_bboxes(&mut frame, &pipeline.tracker, bbox_scalar, bbox_scalar_inverse);
+                draw_identifiers(&mut frame, &pipeline.tracker, id_scalar, id_scalar_inverse);
+                for zone in pipeline.zones.iter() {
                     zone.draw(&mut frame)?;
                 }
+                for exclusion in pipeline.global_exclusions.iter() {
+                    exclusion.draw(&mut frame)?;
+                }
+            }
+            if streamed {
+                if let Some(streamer) = mjpeg_streamer.as_mut() {
+                    if let Err(err) = streamer.push(&frame) {
+                        eprintln!("Can't push frame to MJPEG stream due the error: {}", err);
+                    }
+                }
+            }
+            if let Some(window) = &pipeline.window {
                 // resize(&frame_background, &mut resized_frame, Size::new(self.output.width, self.output.height), 1.0, 1.0, 1)?;
                 resize(&frame, &mut resized_frame, Size::new(self.output.width, self.output.height), 1.0, 1.0, 1)?;
                 if resized_frame.size()?.width > 0 {
@@ -231,10 +445,377 @@ impl App {
                     break;
                 }
             }
+            health().frame_processed();
+            frames_processed += 1;
+            pipeline.frame_index += 1;
+            pipeline.last_frame_time = Some(received.time);
+        }
+        drop(_detection_alive);
+
+        // Consumers should not be left with incidents which never end
+        for pipeline in pipelines.iter_mut() {
+            let frame_time = match pipeline.last_frame_time {
+                Some(frame_time) => frame_time,
+                None => continue,
+            };
+            for zone in pipeline.zones.iter_mut() {
+                for finished_event in zone.close_incidents(&frame_time, Some(app_name.clone()), None) {
+                    send_event(&events_sender, finished_event);
+                    events_emitted += 1;
+                }
+            }
+        }
+
+        // Stop every background thread which holds events sender. Capture threads exit on their own when detection has finished
+        shutdown.store(true, Ordering::Relaxed);
+        drop(scheduler);
+        let mut frames_read: u64 = 0;
+        for (camera_id, capture_thread) in capture_threads {
+            match capture_thread.join() {
+                Ok(camera_frames_read) => frames_read += camera_frames_read,
+                Err(_) => eprintln!("Capture thread of camera '{}' has panicked", camera_id),
+            };
+            if let Some(reason) = health().get_capture_stop_reason(&camera_id) {
+                println!("Video capture of camera '{}' has been stopped: {}", camera_id, reason);
+            }
         }
 
+        // Publishing thread finishes when every sender has been dropped
+        drop(events_sender);
+        drop(system_events_sender);
+        let drain_timeout = Duration::from_secs(self.shutdown.as_ref().and_then(|ss| ss.drain_timeout_seconds).unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECONDS));
+        println!("Waiting for publishers to deliver remaining events (up to {:?})", drain_timeout);
+        match publishing_done_rx.recv_timeout(drain_timeout) {
+            Ok(_) => println!("Every event has been handled by publishers"),
+            Err(_) => eprintln!("[WARNING]: Publishers have not finished in {:?}. Events in flight could be lost (undelivered events are kept in outbox if it is enabled)", drain_timeout),
+        }
+
+        println!("Summary:\n\tUptime: {:.1}s\n\tFrames read: {}\n\tFrames processed: {}\n\tEvents emitted: {}", started_at.elapsed().as_secs_f32(), frames_read, frames_processed, events_emitted);
         Ok(())
     }
+    /// Creates background model, tracker, zones and output window for the camera
+    fn prepare_pipeline(&self, camera: &app_settings::CameraSettings, width: f32, height: f32, fps: f32) -> Result<CameraPipeline, AppError> {
+        let window = if self.output.enable {
+            let window = if self.cameras.len() > 1 { format!("{} [{}]", self.output.window_name, camera.id) } else { self.output.window_name.clone() };
+            named_window(&window, 1)?;
+            resize_window(&window, self.output.width, self.output.height)?;
+            Some(window)
+        } else {
+            None
+        };
+        let bg_subtractor = BackgroundModel::new(&self.background, fps)?;
+        println!("Background model for camera '{}' initialized with following settings:\n\t{}", camera.id, self.background);
+        let static_foreground = match &self.static_foreground {
+            Some(params) => {
+                println!("Static foreground for camera '{}' initialized with following settings:\n\t{}", camera.id, params);
+                Some(StaticForeground::new(params, fps)?)
+            },
+            None => None
+        };
+        let tracker: Tracker = Tracker::new(fps.floor() as usize, 0.3);
+        println!("Tracker for camera '{}' initialized with following settings:\n\t{}", camera.id, tracker);
+        let (global_exclusions, zones) = self.prepare_zones(camera, width, height)?;
+        let target_classes = collect_target_classes(&zones);
+        let processing_size = self.processing_size(width, height);
+        let tiles = self.prepare_tiles(&zones, processing_size)?;
+        if self.tiling.is_some() {
+            println!("Detection for camera '{}' is done on {} tile(s) of {}x{} frame", camera.id, tiles.len(), processing_size.width, processing_size.height);
+        }
+        Ok(CameraPipeline {
+            settings: camera.clone(),
+            width,
+            height,
+            fps,
+            processing_size,
+            scale_width: width / processing_size.width as f32,
+            scale_height: height / processing_size.height as f32,
+            reset_background: camera.input.reconnect.as_ref().and_then(|rs| rs.reset_background).unwrap_or(false),
+            bg_subtractor,
+            static_foreground,
+            foreground_mask: Mat::default(),
+            resized_frame_for_bg: Mat::default(),
+            tracker,
+            global_exclusions,
+            zones,
+            target_classes,
+            zones_outdated: false,
+            window,
+            fps_meter: FpsMeter::new(),
+            frame_index: 0,
+            last_frame_time: None,
+            tiles,
+        })
+    }
+    /// Size of the images the background model and the detector work with. Source resolution is kept for tiling
+    /// (for any tiling areas), so background subtraction costs more CPU then
+    fn processing_size(&self, width: f32, height: f32) -> Size {
+        match self.tiling {
+            Some(_) => Size::new(width as i32, height as i32),
+            None => Size::new(self.detection.net_width, self.detection.net_height),
+        }
+    }
+    /// Tiles of the processed image: either the whole image or bounding rectangles of zones are covered
+    fn prepare_tiles(&self, zones: &[Zone], processing_size: Size) -> Result<Vec<Rect>, AppError> {
+        let tiling = match &self.tiling {
+            Some(tiling) => tiling,
+            None => return Ok(vec![])
+        };
+        let frame_area = Rect::new(0, 0, processing_size.width, processing_size.height);
+        let areas = match tiling.areas {
+            TilingAreas::Frame => vec![frame_area],
+            // Processing size equals to source frame size when tiling is enabled, so zones could be used as is
+            TilingAreas::Zones => zones.iter().map(|zone| zone.bounding_rect().map(|rect| rect & frame_area)).collect::<Result<Vec<Rect>, _>>()?,
+        };
+        Ok(areas.into_iter().flat_map(|area| make_tiles(area, tiling)).collect())
+    }
+    /// Creates global exclusions and zones (or single zone for the whole frame) of the camera from current settings
+    fn prepare_zones(&self, camera: &app_settings::CameraSettings, width: f32, height: f32) -> Result<(Vec<ExclusionPolygon>, Vec<Zone>), AppError> {
+        let zone_rules_defaults = app_settings::default_zone_rules(&self.detection, &self.tracking);
+        let global_exclusions: Vec<ExclusionPolygon> = camera.exclusions.iter().flatten().map(|exclusion_settings| exclusion_settings.to_polygon(width, height)).collect::<Result<Vec<ExclusionPolygon>, AppError>>()?;
+        let zones: Vec<Zone> = match &camera.zones {
+            Some(d) if !d.is_empty() => {
+                d.iter().map(|zone_settings| {
+                    zone_settings.to_zone(width, height, global_exclusions.clone(), &zone_rules_defaults)
+                }).collect::<Result<Vec<Zone>, AppError>>()?
+            }
+            _ => {
+                let mut zone = Zone::new("whole_image".to_string(), &[[5, 5], [width as i32 - 5, 5], [width as i32 - 5, height as i32 - 5], [5, height as i32 - 5]], Some([0, 0, 255]), zone_rules_defaults.clone());
+                zone.set_exclusions(vec![], global_exclusions.clone());
+                vec![zone]
+            }
+        };
+        let zones = zones.into_iter().map(|zone| zone.with_camera_id(camera.id.clone())).collect();
+        Ok((global_exclusions, zones))
+    }
+}
+
+/// Detections are filtered by union of zones' classes. Every zone applies its own classes filter later
+fn collect_target_classes(zones: &[Zone]) -> HashSet<String> {
+    if zones.iter().any(|zone| zone.rules.target_classes.is_empty()) {
+        HashSet::new()
+    } else {
+        zones.iter().flat_map(|zone| zone.rules.target_classes.iter().cloned()).collect()
+    }
+}
+
+/// Message for the publishing thread
+enum PublishMessage {
+    Event(EventInfo),
+    /// System events are published directly, bypassing the outbox
+    System(SystemEvent),
+}
+
+/// Marks detection loop as dead when it exits for any reason (including errors)
+struct DetectionAliveGuard;
+
+impl DetectionAliveGuard {
+    fn new() -> Self {
+        health().set_detection_alive(true);
+        DetectionAliveGuard
+    }
+}
+
+impl Drop for DetectionAliveGuard {
+    fn drop(&mut self) {
+        health().set_detection_alive(false);
+    }
+}
+
+/// Passes event to the publishing thread. Time spent waiting for the thread is accounted as backpressure
+fn send_event(events_sender: &mpsc::SyncSender<PublishMessage>, event: EventInfo) {
+    let send_started = Instant::now();
+    match events_sender.send(PublishMessage::Event(event)) {
+        Ok(_)=>{ },
+        Err(_err) => {
+            // Closed channel?
+            eprintln!("Error on send event to postprocess thread: {}", _err)
+        }
+    };
+    metrics().events_channel_wait_seconds.observe(send_started.elapsed().as_secs_f64());
+}
+
+/// Everything capture thread needs besides the capture itself
+struct CaptureParams {
+    /// Index of the camera in scheduler. Sent along with every frame
+    camera_idx: usize,
+    camera_id: String,
+    video_source: String,
+    video_source_typ: String,
+    fps: f32,
+    /// Frames above this rate are read but not passed to detection
+    max_fps: Option<f32>,
+    clock: Clock,
+    check_frozen: bool,
+    /// None means capture is finished on the first failure
+    reconnect_policy: Option<ReconnectPolicy>,
+    system_events_sender: Option<mpsc::SyncSender<PublishMessage>>,
+    equipment_id: String,
+    shutdown: Arc<AtomicBool>,
+}
+
+/// Reads frames and passes them to the detection thread. Reopens the source on failures if reconnect policy is set.
+/// Returns number of frames read
+fn capture_frames(mut video_capture: VideoCapture, params: CaptureParams, tx_capture: SourceSender<(usize, ThreadedFrame)>) -> u64 {
+    let is_file = detect_time_source(&params.video_source, &params.video_source_typ) == TimeSource::Media;
+    let mut fps = params.fps;
+    let mut frames_since_open: u64 = 0;
+    let mut frames_counter: f32 = 0.0;
+    let mut total_seconds: f32 = 0.0;
+    let mut overall_seconds: f32 = 0.0;
+    let mut empty_frames_countrer: u16 = 0;
+    let mut frames_read: u64 = 0;
+    let mut fps_meter = FpsMeter::new();
+    let mut previous_frame = Mat::default();
+    // Media offset of the source before reconnection. Keeps the timeline monotonic for streams
+    let mut media_offset_base_ms: i64 = 0;
+    let mut last_media_offset_ms: i64 = 0;
+    let mut reconnected = false;
+    let min_frame_interval_ms = params.max_fps.map(|max_fps| (1000.0 / max_fps).round() as i64);
+    let mut last_sent_ms: Option<i64> = None;
+    let stop_reason: String = loop {
+        if params.shutdown.load(Ordering::Relaxed) {
+            break "Shutdown has been requested".to_string();
+        }
+        let mut read_frame = Mat::default();
+        let failure = match video_capture.read(&mut read_frame) {
+            Ok(_) if read_frame.empty() => {
+                println!("[WARNING]: Empty frame from camera '{}'", params.camera_id);
+                metrics().empty_frames.with_label_values(&[&params.camera_id]).inc();
+                empty_frames_countrer += 1;
+                if empty_frames_countrer >= EMPTY_FRAMES_LIMIT {
+                    println!("Too many empty frames");
+                    Some("Too many empty frames")
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => None,
+            Err(_) => {
+                println!("Can't read next frame");
+                Some("Can't read next frame")
+            }
+        };
+        if let Some(reason) = failure {
+            // Frame count of the container could be wrong, so any failed read of the file is considered as its end
+            if is_file {
+                break format!("End of file ({})", reason);
+            }
+            let policy = match &params.reconnect_policy {
+                Some(policy) => policy,
+                None => break reason.to_string(),
+            };
+            send_system_event(&params.system_events_sender, &params.equipment_id, Some(&params.camera_id), SystemEventType::AlertRaised, SystemAlert::SourceLost, reason);
+            if let Err(err) = video_capture.release() {
+                eprintln!("Can't release video capturer due the error: {}", err);
+            }
+            match reopen_video_capture(&params.video_source, &params.video_source_typ, policy, &params.shutdown) {
+                Ok((new_capture, attempts)) => {
+                    video_capture = new_capture;
+                    if let Ok((_, _, new_fps)) = probe_video(&video_capture) {
+                        if new_fps > 0.0 {
+                            fps = new_fps;
+                        }
+                    }
+                    media_offset_base_ms = last_media_offset_ms;
+                    frames_since_open = 0;
+                    empty_frames_countrer = 0;
+                    reconnected = true;
+                    let message = format!("Source of camera '{}' has been reconnected after {} attempt(s)", params.camera_id, attempts);
+                    println!("{}", message);
+                    send_system_event(&params.system_events_sender, &params.equipment_id, Some(&params.camera_id), SystemEventType::SourceReconnected, SystemAlert::SourceLost, &message);
+                    send_system_event(&params.system_events_sender, &params.equipment_id, Some(&params.camera_id), SystemEventType::AlertCleared, SystemAlert::SourceLost, &message);
+                    continue;
+                },
+                Err(err) => {
+                    break format!("{}. {}", reason, err);
+                }
+            }
+        }
+        let identical_to_previous = if params.check_frozen {
+            let identical = previous_frame.size().ok() == read_frame.size().ok() && norm2(&read_frame, &previous_frame, NORM_INF, &no_array()).map(|diff| diff == 0.0).unwrap_or(false);
+            if let Err(err) = read_frame.copy_to(&mut previous_frame) {
+                eprintln!("Can't copy frame for frozen frames check: {}", err);
+            }
+            identical
+        } else {
+            false
+        };
+        health().frame_read(&params.camera_id, identical_to_previous);
+        // Prefer container's timestamps. Fallback to frame counter if they are not available
+        let pos_msec = video_capture.get(CAP_PROP_POS_MSEC).unwrap_or(0.0);
+        let media_offset_ms = media_offset_base_ms + if pos_msec > 0.0 { pos_msec.round() as i64 } else { (frames_since_open as f32 * 1000.0 / fps).round() as i64 };
+        last_media_offset_ms = media_offset_ms;
+        frames_since_open += 1;
+        frames_read += 1;
+        metrics().frames_read.with_label_values(&[&params.camera_id]).inc();
+        if let Some(capture_fps) = fps_meter.tick() {
+            metrics().capture_fps.with_label_values(&[&params.camera_id]).set(capture_fps);
+        }
+        frames_counter += 1.0;
+        let second_fraction = total_seconds + (frames_counter / fps);
+        if frames_counter >= fps {
+            total_seconds += 1.0;
+            overall_seconds += 1.0;
+            frames_counter = 0.0;
+        }
+        let time = params.clock.frame_time(media_offset_ms);
+        if let Some(min_interval_ms) = min_frame_interval_ms {
+            if last_sent_ms.is_some_and(|sent_ms| time.unix_ms - sent_ms < min_interval_ms) {
+                continue;
+            }
+            last_sent_ms = Some(time.unix_ms);
+        }
+        let frame = ThreadedFrame{
+            frame: read_frame,
+            overall_seconds,
+            current_second: second_fraction,
+            time,
+            reconnected,
+        };
+        reconnected = false;
+        let send_started = Instant::now();
+        if tx_capture.send((params.camera_idx, frame)).is_err() {
+            // Detection thread has finished
+            break "Detection has been stopped".to_string();
+        }
+        metrics().frames_channel_wait_seconds.observe(send_started.elapsed().as_secs_f64());
+    };
+    health().capture_stopped(&params.camera_id, &stop_reason);
+    match video_capture.release() {
+        Ok(_) => {
+            println!("Video capture of camera '{}' has been closed successfully", params.camera_id);
+        },
+        Err(err) => {
+            eprintln!("Can't release video capturer due the error: {}", err);
+        }
+    };
+    frames_read
+}
+
+/// Sets returned flag on SIGINT/SIGTERM. Second signal terminates the process immediately
+fn install_shutdown_handler() -> Result<Arc<AtomicBool>, AppError> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let flag = shutdown.clone();
+    ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::Relaxed) {
+            eprintln!("Shutdown has been forced");
+            std::process::exit(130);
+        }
+        println!("Shutdown has been requested. Finishing...");
+    }).map_err(|err| AppError::from(AppInternalError{typ: 12, txt: err.to_string()}))?;
+    Ok(shutdown)
+}
+
+/// Passes system event to the publishing thread if publishing of system events is enabled
+fn send_system_event(sender: &Option<mpsc::SyncSender<PublishMessage>>, equipment_id: &str, camera_id: Option<&str>, event_type: SystemEventType, alert: SystemAlert, message: &str) {
+    let sender = match sender {
+        Some(sender) => sender,
+        None => return,
+    };
+    let system_event = SystemEvent::new(chrono::Utc::now().timestamp(), event_type, alert, message.to_string(), Some(equipment_id.to_string()), camera_id.map(|id| id.to_string()));
+    if let Err(_err) = sender.send(PublishMessage::System(system_event)) {
+        eprintln!("Error on send system event to postprocess thread: {}", _err)
+    }
 }
 
 fn probe_video(capture: &VideoCapture) ->  Result<(f32, f32, f32), AppError> {
@@ -254,12 +835,8 @@ fn probe_video(capture: &VideoCapture) ->  Result<(f32, f32, f32), AppError> {
     Ok((frame_cols, frame_rows, fps))
 }
 
-fn prepare_neural_net(mf: ModelFormat, mv: ModelVersion, weights: &str, configuration: Option<String>, net_size: (i32, i32)) -> Result<Box<dyn ModelTrait>, AppError> {
-
-    /* Check if CUDA is an option at all */
-    let cuda_count = get_cuda_enabled_device_count()?;
-    let cuda_available = cuda_count > 0;
-    println!("CUDA is {}", if cuda_available { "'available'" } else { "'not available'" });
+fn prepare_neural_net(mf: ModelFormat, mv: ModelVersion, weights: &str, configuration: Option<String>, net_size: (i32, i32), backend: DetectorBackend, target: Option<DetectorTarget>) -> Result<Box<dyn Detector>, AppError> {
+    let (backend_id, target_id) = resolve_dnn_backend(backend, target)?;
     println!("Model format is '{:?}'", mf);
     println!("Model type is '{:?}'", mv);
 
@@ -271,22 +848,138 @@ fn prepare_neural_net(mf: ModelFormat, mv: ModelVersion, weights: &str, configur
         configuration_str,
         (net_size.0, net_size.1),
         mf, mv,
-        if cuda_available { DNN_BACKEND_CUDA } else { DNN_BACKEND_OPENCV },
-        if cuda_available { DNN_TARGET_CUDA } else { DNN_TARGET_CPU },
+        backend_id,
+        target_id,
         vec![]
     )?;
-    Ok(neural_net)
+    Ok(Box::new(OpenCVDetector::new(neural_net)))
 }
 
-fn events_processing(events_reciever: mpsc::Receiver<EventInfo>, publishers: Vec<Box<dyn PublisherTrait>>) {
-    for event_income in events_reciever {
-        for publisher in publishers.iter() {
-            match publisher.publish(&event_income) {
+/// Runs detector on the whole image or on its tiles if tiling is enabled
+fn detect(detector: &mut dyn Detector, image: &Mat, tiles: &[Rect], tiling: Option<&TilingParams>, frame_index: u64, conf_threshold: f32, nms_threshold: f32) -> Result<DetectorOutput, DetectorError> {
+    match tiling {
+        Some(tiling) => detect_tiled(detector, image, tiles, tiling.include_full_image, frame_index, conf_threshold, nms_threshold),
+        None => detector.detect(image, frame_index, conf_threshold, nms_threshold),
+    }
+}
+
+/// Maps backend and target to identifiers of OpenCV's DNN module. Fails if OpenCV has not been built with them
+fn resolve_dnn_backend(backend: DetectorBackend, target: Option<DetectorTarget>) -> Result<(i32, i32), AppError> {
+    let (backend, target) = match (backend, target) {
+        (DetectorBackend::Auto, _) => {
+            /* Check if CUDA is an option at all */
+            let cuda_count = get_cuda_enabled_device_count()?;
+            let cuda_available = cuda_count > 0;
+            println!("CUDA is {}", if cuda_available { "'available'" } else { "'not available'" });
+            if cuda_available { (DetectorBackend::Cuda, DetectorTarget::Cuda) } else { (DetectorBackend::OpenCV, DetectorTarget::Cpu) }
+        },
+        (backend, Some(target)) => (backend, target),
+        (backend, None) => {
+            match backend.supported_targets().first() {
+                Some(target) => (backend, *target),
+                None => return Err(AppError::from(AppInternalError{typ: 14, txt: format!("No target for backend {:?}", backend)}))
+            }
+        }
+    };
+    let backend_id = match backend {
+        DetectorBackend::OpenCV => DNN_BACKEND_OPENCV,
+        DetectorBackend::Cuda => DNN_BACKEND_CUDA,
+        DetectorBackend::OpenVINO => DNN_BACKEND_INFERENCE_ENGINE,
+        _ => return Err(AppError::from(AppInternalError{typ: 14, txt: format!("{:?} is not a backend of OpenCV's DNN module", backend)}))
+    };
+    let target_id = match target {
+        DetectorTarget::Cpu => DNN_TARGET_CPU,
+        DetectorTarget::OpenCL => DNN_TARGET_OPENCL,
+        DetectorTarget::OpenCLFP16 => DNN_TARGET_OPENCL_FP16,
+        DetectorTarget::Cuda => DNN_TARGET_CUDA,
+        DetectorTarget::CudaFP16 => DNN_TARGET_CUDA_FP16,
+        DetectorTarget::Myriad => DNN_TARGET_MYRIAD,
+    };
+    // Legacy Inference Engine constant is reported by old builds only
+    let accepted_backends = match backend {
+        DetectorBackend::OpenVINO => vec![DNN_BACKEND_INFERENCE_ENGINE, DNN_BACKEND_INFERENCE_ENGINE_NGRAPH],
+        _ => vec![backend_id],
+    };
+    let available = get_available_backends()?;
+    let is_available = available.iter().any(|pair| {
+        let (available_backend, available_target) = pair.into_tuple();
+        accepted_backends.contains(&(available_backend as i32)) && available_target as i32 == target_id
+    });
+    if !is_available {
+        let available_str = available.iter().map(|pair| format!("{:?}", pair.into_tuple())).collect::<Vec<String>>().join(", ");
+        return Err(AppError::from(AppInternalError{typ: 14, txt: format!("backend {:?} with target {:?} is not compiled into OpenCV. Available: [{}]", backend, target, available_str)}));
+    }
+    println!("Neural network backend is '{:?}', target is '{:?}'", backend, target);
+    Ok((backend_id, target_id))
+}
+
+fn publish_system_event(publishers: &[PublisherEntry], event: &SystemEvent) {
+    for entry in publishers.iter() {
+        if let Err(err) = entry.publish_system(event) {
+            eprintln!("Error during publishing system event via '{}': {:#?}", entry.name, err);
+        }
+    }
+}
+
+fn events_processing(events_reciever: mpsc::Receiver<PublishMessage>, publishers: Vec<PublisherEntry>) {
+    for message in events_reciever {
+        let event_income = match message {
+            PublishMessage::Event(event) => event,
+            PublishMessage::System(system_event) => {
+                publish_system_event(&publishers, &system_event);
+                continue;
+            }
+        };
+        for entry in publishers.iter() {
+            match entry.publish(&event_income) {
                 Ok(_) => {},
                 Err(err) => {
-                    eprintln!("Error during publishing message: {:#?}", err);
+                    eprintln!("Error during publishing message via '{}': {:#?}", entry.name, err);
                 }
             };
         }
     }
 }
+
+fn events_processing_with_outbox(events_reciever: mpsc::Receiver<PublishMessage>, publishers: Vec<PublisherEntry>, mut outbox: Outbox) {
+    loop {
+        // Wake up either on new event or when the closest retry should be done
+        let timeout = outbox.next_due().map(|due| due.saturating_duration_since(Instant::now())).unwrap_or(Duration::from_secs(1));
+        match events_reciever.recv_timeout(timeout) {
+            Ok(PublishMessage::System(system_event)) => {
+                publish_system_event(&publishers, &system_event);
+            },
+            Ok(PublishMessage::Event(event_income)) => {
+                match outbox.push(&event_income) {
+                    Ok(Some(seq)) => {
+                        if let Err(err) = outbox.deliver_event(seq, &event_income, &publishers) {
+                            eprintln!("Outbox error: {}", err);
+                        }
+                    },
+                    Ok(None) => {},
+                    Err(err) => {
+                        eprintln!("Can't store event '{}' in outbox: {}", event_income.get_id(), err);
+                    }
+                }
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => {},
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                break;
+            }
+        }
+        if let Err(err) = outbox.deliver_due(&publishers) {
+            eprintln!("Outbox error: {}", err);
+        }
+        metrics().outbox_pending.set(outbox.len() as i64);
+    }
+    match outbox.flush() {
+        Ok(_) => {
+            if !outbox.is_empty() {
+                println!("Outbox keeps {} undelivered event(s) until the next start", outbox.len());
+            }
+        },
+        Err(err) => {
+            eprintln!("Can't flush outbox: {}", err);
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use crate::video_capture;
 use crate::zones::zones_error;
+use crate::mjpeg::MjpegError;
+use crate::rest_api::RestApiError;
+use crate::detector::DetectorError;
 use std::fmt;
 use toml;
 
@@ -13,6 +16,15 @@ impl fmt::Display for AppInternalError {
             3 => write!(f, "Bad model format: '{}'", self.txt),
             4 => write!(f, "Bad model version: '{}'", self.txt),
             5 => write!(f, "Bad tracker parameters: '{}'", self.txt),
+            6 => write!(f, "Bad outbox parameters: '{}'", self.txt),
+            7 => write!(f, "Bad publisher parameters: '{}'", self.txt),
+            8 => write!(f, "Bad zone parameters: '{}'", self.txt),
+            9 => write!(f, "Bad input parameters: '{}'", self.txt),
+            10 => write!(f, "Bad MJPEG streaming parameters: '{}'", self.txt),
+            11 => write!(f, "Bad detection parameters: '{}'", self.txt),
+            12 => write!(f, "Can't install signal handler: '{}'", self.txt),
+            13 => write!(f, "Bad background parameters: '{}'", self.txt),
+            14 => write!(f, "Neural network backend is not available: '{}'", self.txt),
             _ => write!(f, "Undefined VideoCapture error")
         }
     }
@@ -24,7 +36,29 @@ pub enum AppError {
     VideoError(video_capture::VideoCaptureError),
     OpenCVError(opencv::Error),
     TOMLError(toml::de::Error),
+    TOMLSerError(toml::ser::Error),
+    IOError(std::io::Error),
     ZonesErrorInternal(zones_error::ZonesError),
+    MjpegErrorInternal(MjpegError),
+    RestApiErrorInternal(RestApiError),
+    DetectorErrorInternal(DetectorError),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Internal(e) => write!(f, "{}", e),
+            AppError::VideoError(e) => write!(f, "{}", e),
+            AppError::OpenCVError(e) => write!(f, "{}", e),
+            AppError::TOMLError(e) => write!(f, "{}", e),
+            AppError::TOMLSerError(e) => write!(f, "{}", e),
+            AppError::IOError(e) => write!(f, "{}", e),
+            AppError::ZonesErrorInternal(e) => write!(f, "{}", e),
+            AppError::MjpegErrorInternal(e) => write!(f, "{}", e),
+            AppError::RestApiErrorInternal(e) => write!(f, "{}", e),
+            AppError::DetectorErrorInternal(e) => write!(f, "{}", e),
+        }
+    }
 }
 
 impl From<AppInternalError> for AppError {
@@ -56,3 +90,33 @@ impl From<zones_error::ZonesError> for AppError {
         AppError::ZonesErrorInternal(e)
     }
 }
+
+impl From<MjpegError> for AppError {
+    fn from(e: MjpegError) -> Self {
+        AppError::MjpegErrorInternal(e)
+    }
+}
+
+impl From<toml::ser::Error> for AppError {
+    fn from(e: toml::ser::Error) -> Self {
+        AppError::TOMLSerError(e)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::IOError(e)
+    }
+}
+
+impl From<RestApiError> for AppError {
+    fn from(e: RestApiError) -> Self {
+        AppError::RestApiErrorInternal(e)
+    }
+}
+
+impl From<DetectorError> for AppError {
+    fn from(e: DetectorError) -> Self {
+        AppError::DetectorErrorInternal(e)
+    }
+}
@@ -1,4 +1,6 @@
 pub mod app_error;
 pub mod app_settings;
+pub mod runtime_settings;
+pub mod scheduler;
 pub mod app;
-pub use self::{app_error::*, app_settings::*, app::*};
+pub use self::{app_error::*, app_settings::*, runtime_settings::*, scheduler::*, app::*};
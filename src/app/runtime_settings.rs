@@ -0,0 +1,71 @@
+use crate::app::{AppSettings, AppError, AppInternalError, default_zone_rules};
+
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Settings shared between the detection loop and REST API.
+/// Every accepted change bumps the version, so the loop knows when to re-read them
+pub struct RuntimeSettings {
+    settings: RwLock<AppSettings>,
+    version: AtomicU64,
+    /// Size of the frames by camera identifier. Known only after the video has been opened
+    frame_sizes: Mutex<HashMap<String, (f32, f32)>>,
+    /// Configuration file to write accepted changes to
+    persist_path: Option<String>,
+}
+
+impl RuntimeSettings {
+    pub fn new(settings: AppSettings, persist_path: Option<String>) -> Self {
+        RuntimeSettings {
+            settings: RwLock::new(settings),
+            version: AtomicU64::new(0),
+            frame_sizes: Mutex::new(HashMap::new()),
+            persist_path,
+        }
+    }
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+    pub fn snapshot(&self) -> AppSettings {
+        self.settings.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    pub fn set_frame_size(&self, camera_id: &str, width: f32, height: f32) {
+        self.frame_sizes.lock().unwrap_or_else(|e| e.into_inner()).insert(camera_id.to_string(), (width, height));
+    }
+    /// Applies change to the copy of current settings and makes it current one if the copy passes validation
+    pub fn update<F>(&self, change: F) -> Result<AppSettings, AppError>
+    where
+        F: FnOnce(&mut AppSettings) -> Result<(), AppError>
+    {
+        let mut settings = self.settings.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = settings.clone();
+        change(&mut updated)?;
+        updated.validate()?;
+        // Tiles are prepared once the detector is started, so the change would be silently ignored
+        if updated.detection.get_tiling()? != settings.detection.get_tiling()? {
+            return Err(AppError::from(AppInternalError{typ: 11, txt: "detection.tiling could not be changed at runtime: edit configuration file and restart the application".to_string()}));
+        }
+        // Pixel coordinates could be checked against frame bounds only when the frame size is known
+        let frame_sizes = self.frame_sizes.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let defaults = default_zone_rules(&updated.detection, &updated.tracking);
+        for camera in updated.cameras() {
+            let (width, height) = match frame_sizes.get(&camera.id) {
+                Some(size) => *size,
+                None => continue,
+            };
+            for exclusion_settings in camera.exclusions.iter().flatten() {
+                exclusion_settings.to_polygon(width, height)?;
+            }
+            for zone_settings in camera.zones.iter().flatten() {
+                zone_settings.to_zone(width, height, vec![], &defaults)?;
+            }
+        }
+        if let Some(path) = &self.persist_path {
+            updated.save_to_file(path)?;
+        }
+        *settings = updated.clone();
+        self.version.fetch_add(1, Ordering::AcqRel);
+        Ok(updated)
+    }
+}
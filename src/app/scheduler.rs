@@ -0,0 +1,150 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+// Granularity of checking for stop request while waiting for frames
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Order in which frames of different video sources are taken by the detection loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchedulingStrategy {
+    /// Sources take turns. Fast source can't starve slow ones
+    RoundRobin,
+    /// Frames are processed in order of arrival
+    Fifo,
+}
+
+impl FromStr for SchedulingStrategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "round_robin" => Ok(SchedulingStrategy::RoundRobin),
+            "fifo" => Ok(SchedulingStrategy::Fifo),
+            _ => Err(format!("Bad scheduling strategy: '{}'. Available: 'round_robin', 'fifo'", s)),
+        }
+    }
+}
+
+/// Frames waiting for the detection loop: at most one per source
+struct Slots<T> {
+    /// Frame of every source along with its arrival number
+    items: Vec<Option<(u64, T)>>,
+    /// Sources which capture threads are still running
+    alive: Vec<bool>,
+    arrivals: u64,
+    /// Detection loop has stopped taking frames
+    closed: bool,
+}
+
+struct Shared<T> {
+    slots: Mutex<Slots<T>>,
+    /// Notified when frame is put, taken or when any side disconnects
+    changed: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, Slots<T>> {
+        // Slots stay consistent even if some thread has panicked while holding the lock
+        self.slots.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Sending side of the single source. Send blocks while previous frame of the source has not been taken yet
+pub struct SourceSender<T> {
+    idx: usize,
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> SourceSender<T> {
+    /// Returns the item back if the detection loop has stopped
+    pub fn send(&self, item: T) -> Result<(), mpsc::SendError<T>> {
+        let mut slots = self.shared.lock();
+        while slots.items[self.idx].is_some() && !slots.closed {
+            slots = self.shared.changed.wait(slots).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        if slots.closed {
+            return Err(mpsc::SendError(item));
+        }
+        slots.arrivals += 1;
+        let arrival = slots.arrivals;
+        slots.items[self.idx] = Some((arrival, item));
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+}
+
+impl<T> Drop for SourceSender<T> {
+    fn drop(&mut self) {
+        let mut slots = self.shared.lock();
+        slots.alive[self.idx] = false;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// Collects frames from several capture threads and hands them to the detection loop one by one.
+/// Detection loop sleeps until any source has a frame, so idle sources cost nothing
+pub struct FrameScheduler<T> {
+    strategy: SchedulingStrategy,
+    shared: Arc<Shared<T>>,
+    cursor: usize,
+}
+
+impl<T> FrameScheduler<T> {
+    pub fn new(strategy: SchedulingStrategy) -> Self {
+        FrameScheduler {
+            strategy,
+            shared: Arc::new(Shared {
+                slots: Mutex::new(Slots { items: vec![], alive: vec![], arrivals: 0, closed: false }),
+                changed: Condvar::new(),
+            }),
+            cursor: 0,
+        }
+    }
+    /// Registers new source. Returned sender should be used by the capture thread of the source
+    pub fn add_source(&mut self) -> SourceSender<T> {
+        let mut slots = self.shared.lock();
+        slots.items.push(None);
+        slots.alive.push(true);
+        SourceSender { idx: slots.items.len() - 1, shared: self.shared.clone() }
+    }
+    /// Waits for the next frame. Returns None when every source has finished or `stop` is set
+    pub fn next(&mut self, stop: &AtomicBool) -> Option<T> {
+        let shared = self.shared.clone();
+        let mut slots = shared.lock();
+        loop {
+            if stop.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Some(idx) = pick(self.strategy, self.cursor, &slots) {
+                let (_, item) = slots.items[idx].take()?;
+                self.cursor = (idx + 1) % slots.items.len();
+                shared.changed.notify_all();
+                return Some(item);
+            }
+            if !slots.alive.iter().any(|alive| *alive) {
+                return None;
+            }
+            slots = shared.changed.wait_timeout(slots, STOP_CHECK_INTERVAL).map(|(guard, _)| guard).unwrap_or_else(|poisoned| poisoned.into_inner().0);
+        }
+    }
+}
+
+/// Source which frame should be taken next
+fn pick<T>(strategy: SchedulingStrategy, cursor: usize, slots: &Slots<T>) -> Option<usize> {
+    let sources_number = slots.items.len();
+    match strategy {
+        SchedulingStrategy::RoundRobin => (0..sources_number).map(|step| (cursor + step) % sources_number).find(|idx| slots.items[*idx].is_some()),
+        SchedulingStrategy::Fifo => slots.items.iter().enumerate().filter_map(|(idx, item)| item.as_ref().map(|(arrival, _)| (*arrival, idx))).min().map(|(_, idx)| idx),
+    }
+}
+
+impl<T> Drop for FrameScheduler<T> {
+    /// Wakes up capture threads waiting in `send`, so they could finish
+    fn drop(&mut self) {
+        let mut slots = self.shared.lock();
+        slots.closed = true;
+        slots.items.iter_mut().for_each(|item| *item = None);
+        self.shared.changed.notify_all();
+    }
+}
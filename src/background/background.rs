@@ -0,0 +1,139 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "cnt")]
+use opencv::bgsegm::{create_background_subtractor_cnt, BackgroundSubtractorCNT};
+use opencv::{
+    core::{Mat, Ptr},
+    video::{create_background_subtractor_knn, create_background_subtractor_mog2, BackgroundSubtractorKNN, BackgroundSubtractorMOG2, BackgroundSubtractorTrait, BackgroundSubtractorTraitConst},
+};
+
+pub const DEFAULT_HISTORY_SECONDS: f32 = 1.0;
+pub const DEFAULT_MOG2_VAR_THRESHOLD: f64 = 16.0;
+pub const DEFAULT_KNN_DIST2_THRESHOLD: f64 = 400.0;
+/// Negative value lets the algorithm choose learning rate based on history length
+pub const DEFAULT_LEARNING_RATE: f64 = -1.0;
+/// CNT: ratio of max pixel stability to min pixel stability (same as OpenCV's defaults 15 and 15*60)
+pub const DEFAULT_CNT_MAX_STABILITY_FACTOR: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackgroundAlgorithm {
+    MOG2,
+    KNN,
+    /// Requires "cnt" feature (OpenCV built with contrib module bgsegm)
+    CNT,
+}
+
+impl FromStr for BackgroundAlgorithm {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mog2" => Ok(BackgroundAlgorithm::MOG2),
+            "knn" => Ok(BackgroundAlgorithm::KNN),
+            "cnt" => Ok(BackgroundAlgorithm::CNT),
+            _ => Err(format!("Bad background algorithm: '{}'. Available: 'mog2', 'knn', 'cnt'", s)),
+        }
+    }
+}
+
+/// Parameters of the background model. Durations are in seconds and converted to frames with respect to the camera FPS
+#[derive(Debug, Clone)]
+pub struct BackgroundParams {
+    pub algorithm: BackgroundAlgorithm,
+    /// How long an object should stay still before it becomes part of the background image
+    pub history_seconds: f32,
+    /// MOG2: threshold on the squared Mahalanobis distance. KNN: threshold on the squared distance. Not used by CNT
+    pub var_threshold: f64,
+    /// In range [0; 1]. Negative value means automatic choice
+    pub learning_rate: f64,
+    /// MOG2 and KNN only
+    pub detect_shadows: bool,
+    /// CNT only
+    pub use_history: bool,
+    /// CNT only
+    pub max_stability_seconds: f32,
+    /// CNT only
+    pub is_parallel: bool,
+}
+
+impl Default for BackgroundParams {
+    fn default() -> Self {
+        BackgroundParams {
+            algorithm: BackgroundAlgorithm::MOG2,
+            history_seconds: DEFAULT_HISTORY_SECONDS,
+            var_threshold: DEFAULT_MOG2_VAR_THRESHOLD,
+            learning_rate: DEFAULT_LEARNING_RATE,
+            detect_shadows: false,
+            use_history: true,
+            max_stability_seconds: DEFAULT_HISTORY_SECONDS * DEFAULT_CNT_MAX_STABILITY_FACTOR,
+            is_parallel: true,
+        }
+    }
+}
+
+impl fmt::Display for BackgroundParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.algorithm {
+            BackgroundAlgorithm::CNT => write!(f, "Algorithm: {:?} | History: {}s | Max stability: {}s | Use history: {} | Parallel: {} | Learning rate: {}", self.algorithm, self.history_seconds, self.max_stability_seconds, self.use_history, self.is_parallel, self.learning_rate),
+            _ => write!(f, "Algorithm: {:?} | History: {}s | Threshold: {} | Shadows: {} | Learning rate: {}", self.algorithm, self.history_seconds, self.var_threshold, self.detect_shadows, self.learning_rate),
+        }
+    }
+}
+
+enum Subtractor {
+    MOG2(Ptr<BackgroundSubtractorMOG2>),
+    KNN(Ptr<BackgroundSubtractorKNN>),
+    #[cfg(feature = "cnt")]
+    CNT(Ptr<BackgroundSubtractorCNT>),
+}
+
+/// Background subtractor chosen by configuration
+pub struct BackgroundModel {
+    subtractor: Subtractor,
+    learning_rate: f64,
+}
+
+/// Converts duration to number of frames. At least single frame is used
+fn seconds_to_frames(seconds: f32, fps: f32) -> i32 {
+    ((seconds * fps).floor() as i32).max(1)
+}
+
+impl BackgroundModel {
+    pub fn new(params: &BackgroundParams, fps: f32) -> Result<Self, opencv::Error> {
+        let history = seconds_to_frames(params.history_seconds, fps);
+        let subtractor = match params.algorithm {
+            BackgroundAlgorithm::MOG2 => Subtractor::MOG2(create_background_subtractor_mog2(history, params.var_threshold, params.detect_shadows)?),
+            BackgroundAlgorithm::KNN => Subtractor::KNN(create_background_subtractor_knn(history, params.var_threshold, params.detect_shadows)?),
+            #[cfg(feature = "cnt")]
+            BackgroundAlgorithm::CNT => {
+                let max_stability = seconds_to_frames(params.max_stability_seconds, fps);
+                Subtractor::CNT(create_background_subtractor_cnt(history, params.use_history, max_stability, params.is_parallel)?)
+            },
+            #[cfg(not(feature = "cnt"))]
+            BackgroundAlgorithm::CNT => {
+                return Err(opencv::Error::new(opencv::core::StsNotImplemented, "CNT background subtractor requires build with 'cnt' feature"));
+            }
+        };
+        Ok(BackgroundModel {
+            subtractor,
+            learning_rate: params.learning_rate,
+        })
+    }
+    /// Updates the model with the frame and computes the foreground mask
+    pub fn apply(&mut self, frame: &Mat, foreground_mask: &mut Mat) -> Result<(), opencv::Error> {
+        match &mut self.subtractor {
+            Subtractor::MOG2(subtractor) => subtractor.apply(frame, foreground_mask, self.learning_rate),
+            Subtractor::KNN(subtractor) => subtractor.apply(frame, foreground_mask, self.learning_rate),
+            #[cfg(feature = "cnt")]
+            Subtractor::CNT(subtractor) => subtractor.apply(frame, foreground_mask, self.learning_rate),
+        }
+    }
+    pub fn get_background_image(&self, background: &mut Mat) -> Result<(), opencv::Error> {
+        match &self.subtractor {
+            Subtractor::MOG2(subtractor) => subtractor.get_background_image(background),
+            Subtractor::KNN(subtractor) => subtractor.get_background_image(background),
+            #[cfg(feature = "cnt")]
+            Subtractor::CNT(subtractor) => subtractor.get_background_image(background),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod background;
+mod static_foreground;
+
+pub use self::{background::*, static_foreground::*};
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use opencv::{
+    core::{add, bitwise_and, bitwise_not, count_non_zero, mean, multiply, no_array, Mat, Rect, Scalar, CV_32F},
+    imgproc::{threshold, THRESH_BINARY},
+    prelude::MatTraitConst,
+};
+
+use crate::background::{BackgroundModel, BackgroundParams};
+use crate::detection::{iou, RawDetections};
+
+// Foreground masks contain 255 for foreground and 127 for shadows. Shadows are not considered as objects
+const FOREGROUND_THRESHOLD: f64 = 200.0;
+// Detection which overlaps tracked static object at least this much keeps passing the gate
+const HOLD_IOU_THRESHOLD: f32 = 0.5;
+
+/// Parameters of the long-term background model and gating of detections
+#[derive(Debug, Clone)]
+pub struct StaticForegroundParams {
+    /// Long-term model. Its history should be longer than one of the main (short-term) model
+    pub long: BackgroundParams,
+    /// Detections with lower share of static pixels in the bounding box are dropped
+    pub min_static_ratio: f32,
+    /// Detections which have been static for less seconds are dropped
+    pub min_static_seconds: f32,
+}
+
+impl fmt::Display for StaticForegroundParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Long-term model: {} | Min static ratio: {} | Min static seconds: {}", self.long, self.min_static_ratio, self.min_static_seconds)
+    }
+}
+
+/// Dual background scheme. Object which has recently stopped is already absorbed by the short-term model,
+/// but is still foreground for the long-term one. Such pixels form static foreground mask.
+/// Object which has been standing longer than history of the long-term model is the background for both of models
+pub struct StaticForeground {
+    long: BackgroundModel,
+    long_mask: Mat,
+    /// Pixels which are static at current frame (255) or not (0)
+    static_mask: Mat,
+    /// Number of consecutive frames every pixel has been static for
+    evidence: Mat,
+    fps: f32,
+    min_static_ratio: f32,
+    min_static_seconds: f32,
+}
+
+impl StaticForeground {
+    pub fn new(params: &StaticForegroundParams, fps: f32) -> Result<Self, opencv::Error> {
+        Ok(StaticForeground {
+            long: BackgroundModel::new(&params.long, fps)?,
+            long_mask: Mat::default(),
+            static_mask: Mat::default(),
+            evidence: Mat::default(),
+            fps,
+            min_static_ratio: params.min_static_ratio,
+            min_static_seconds: params.min_static_seconds,
+        })
+    }
+    /// Updates the long-term model with the frame and accumulates static evidence. Foreground mask of the short-term model should be computed for the same frame
+    pub fn update(&mut self, frame: &Mat, short_foreground_mask: &Mat) -> Result<(), opencv::Error> {
+        self.long.apply(frame, &mut self.long_mask)?;
+        let mut long_foreground = Mat::default();
+        threshold(&self.long_mask, &mut long_foreground, FOREGROUND_THRESHOLD, 255.0, THRESH_BINARY)?;
+        let mut short_foreground = Mat::default();
+        threshold(short_foreground_mask, &mut short_foreground, FOREGROUND_THRESHOLD, 255.0, THRESH_BINARY)?;
+        let mut short_background = Mat::default();
+        bitwise_not(&short_foreground, &mut short_background, &no_array())?;
+        bitwise_and(&long_foreground, &short_background, &mut self.static_mask, &no_array())?;
+
+        // Evidence is incremented for static pixels and reset for others: evidence = (evidence + step) * step, where step is 0 or 1
+        let mut step = Mat::default();
+        self.static_mask.convert_to(&mut step, CV_32F, 1.0 / 255.0, 0.0)?;
+        if self.evidence.size()? != step.size()? {
+            self.evidence = Mat::new_rows_cols_with_default(step.rows(), step.cols(), CV_32F, Scalar::all(0.0))?;
+        }
+        let mut accumulated = Mat::default();
+        add(&self.evidence, &step, &mut accumulated, &no_array(), -1)?;
+        multiply(&accumulated, &step, &mut self.evidence, 1.0, -1)?;
+        Ok(())
+    }
+    /// Returns share of static pixels in the bounding box and mean time (in seconds) they have been static for.
+    /// Bounding box is expected in coordinates of the frame passed to `update`
+    pub fn measure(&self, bbox: &Rect) -> Result<(f32, f32), opencv::Error> {
+        let x = bbox.x.max(0);
+        let y = bbox.y.max(0);
+        let width = (bbox.x + bbox.width).min(self.static_mask.cols()) - x;
+        let height = (bbox.y + bbox.height).min(self.static_mask.rows()) - y;
+        if width <= 0 || height <= 0 {
+            return Ok((0.0, 0.0));
+        }
+        let roi = Rect::new(x, y, width, height);
+        let static_roi = Mat::roi(&self.static_mask, roi)?;
+        let static_pixels = count_non_zero(&static_roi)?;
+        if static_pixels == 0 {
+            return Ok((0.0, 0.0));
+        }
+        let evidence_roi = Mat::roi(&self.evidence, roi)?;
+        let mean_frames = mean(&evidence_roi, &static_roi)?[0] as f32;
+        Ok((static_pixels as f32 / (width * height) as f32, mean_frames / self.fps))
+    }
+    /// Drops detections which are not static enough and sets static time (in seconds) for remaining ones.
+    /// `held` are boxes (in the same coordinates) and static time of tracked objects which have already passed the gate:
+    /// object standing longer than history of the long-term model becomes background for it, but should not be lost while it is tracked
+    pub fn filter_detections(&self, detections: RawDetections, held: &[(Rect, f32)]) -> Result<RawDetections, opencv::Error> {
+        let mut kept = RawDetections::default();
+        for i in 0..detections.len() {
+            let bbox = detections.bboxes[i];
+            let (ratio, seconds) = self.measure(&bbox)?;
+            let static_seconds = if ratio >= self.min_static_ratio && seconds >= self.min_static_seconds {
+                seconds
+            } else {
+                let held_seconds = held.iter()
+                    .filter(|(held_bbox, _)| iou(held_bbox, &bbox) >= HOLD_IOU_THRESHOLD)
+                    .map(|(_, held_seconds)| *held_seconds)
+                    .max_by(|a, b| a.total_cmp(b));
+                match held_seconds {
+                    Some(held_seconds) => held_seconds.max(seconds) + 1.0 / self.fps,
+                    None => continue,
+                }
+            };
+            kept.push(bbox, detections.classes_ids[i], detections.confidences[i], Some(static_seconds), detections.sources[i]);
+        }
+        Ok(kept)
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+/// Limits for detections of the single class. Sizes are in pixels of the source frame, aspect ratio is width / height
+#[derive(Debug, Clone, Default)]
+pub struct ClassFilter {
+    /// Global threshold is used if not provided
+    pub conf_threshold: Option<f32>,
+    pub min_width: Option<f32>,
+    pub max_width: Option<f32>,
+    pub min_height: Option<f32>,
+    pub max_height: Option<f32>,
+    pub min_aspect_ratio: Option<f32>,
+    pub max_aspect_ratio: Option<f32>,
+}
+
+/// Per-class confidence thresholds and bbox limits
+#[derive(Debug, Clone, Default)]
+pub struct ClassFilters {
+    pub conf_threshold: f32,
+    pub by_class: HashMap<String, ClassFilter>,
+}
+
+impl ClassFilters {
+    pub fn new(conf_threshold: f32, by_class: HashMap<String, ClassFilter>) -> Self {
+        ClassFilters {
+            conf_threshold,
+            by_class,
+        }
+    }
+    /// Threshold to be passed to the neural network: per-class thresholds could be lower than the global one
+    pub fn min_conf_threshold(&self) -> f32 {
+        self.by_class.values().filter_map(|filter| filter.conf_threshold).fold(self.conf_threshold, f32::min)
+    }
+    pub fn allows(&self, classname: &str, confidence: f32, width: f32, height: f32) -> bool {
+        let filter = match self.by_class.get(classname) {
+            Some(filter) => filter,
+            None => return confidence >= self.conf_threshold
+        };
+        if confidence < filter.conf_threshold.unwrap_or(self.conf_threshold) {
+            return false;
+        }
+        let below = |value: f32, limit: Option<f32>| limit.is_some_and(|limit| value < limit);
+        let above = |value: f32, limit: Option<f32>| limit.is_some_and(|limit| value > limit);
+        if below(width, filter.min_width) || above(width, filter.max_width) || below(height, filter.min_height) || above(height, filter.max_height) {
+            return false;
+        }
+        if filter.min_aspect_ratio.is_some() || filter.max_aspect_ratio.is_some() {
+            if height <= 0.0 {
+                return false;
+            }
+            let aspect_ratio = width / height;
+            if below(aspect_ratio, filter.min_aspect_ratio) || above(aspect_ratio, filter.max_aspect_ratio) {
+                return false;
+            }
+        }
+        true
+    }
+}
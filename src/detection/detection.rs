@@ -1,34 +1,155 @@
-
 use opencv::core::Rect as RectCV;
 
 use mot_rs::mot::SimpleBlob;
 use mot_rs::utils::{
     Rect, Point
 };
+use serde::{Serialize, Deserialize};
+
+use crate::detection::ClassFilters;
 
 use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Image the neural network is applied to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetectionInput {
+    /// Background image. Only objects which have been stationary for a while are seen
+    Background,
+    /// Live frame. Moving objects are seen too
+    Frame,
+    /// Both images. Detections are fused
+    Both,
+}
+
+impl DetectionInput {
+    /// Sources detections could come from
+    pub fn sources(&self) -> DetectionSources {
+        match self {
+            DetectionInput::Background => DetectionSources::background(),
+            DetectionInput::Frame => DetectionSources::frame(),
+            DetectionInput::Both => DetectionSources::background().union(&DetectionSources::frame()),
+        }
+    }
+}
+
+impl FromStr for DetectionInput {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "background" => Ok(DetectionInput::Background),
+            "frame" => Ok(DetectionInput::Frame),
+            "both" => Ok(DetectionInput::Both),
+            _ => Err(format!("Bad detection input: '{}'. Available: 'background', 'frame', 'both'", s)),
+        }
+    }
+}
+
+/// Images the object has been detected on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DetectionSources {
+    pub background: bool,
+    pub frame: bool,
+}
+
+impl DetectionSources {
+    pub fn background() -> Self {
+        DetectionSources { background: true, frame: false }
+    }
+    pub fn frame() -> Self {
+        DetectionSources { background: false, frame: true }
+    }
+    pub fn union(&self, other: &DetectionSources) -> Self {
+        DetectionSources {
+            background: self.background || other.background,
+            frame: self.frame || other.frame,
+        }
+    }
+    /// Checks if every required source is present
+    pub fn contains(&self, required: &DetectionSources) -> bool {
+        (!required.background || self.background) && (!required.frame || self.frame)
+    }
+}
+
+impl FromStr for DetectionSources {
+    type Err = String;
+    /// Parses single source name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "background" => Ok(DetectionSources::background()),
+            "frame" => Ok(DetectionSources::frame()),
+            _ => Err(format!("Bad detection source: '{}'. Available: 'background', 'frame'", s)),
+        }
+    }
+}
+
+/// Output of the neural network in coordinates of its input image
+#[derive(Debug, Default)]
+pub struct RawDetections {
+    pub bboxes: Vec<RectCV>,
+    pub classes_ids: Vec<usize>,
+    pub confidences: Vec<f32>,
+    /// How long the region of every detection has been static. None if static foreground is not computed
+    pub static_seconds: Vec<Option<f32>>,
+    pub sources: Vec<DetectionSources>,
+}
+
+impl RawDetections {
+    pub fn new(bboxes: Vec<RectCV>, classes_ids: Vec<usize>, confidences: Vec<f32>, source: DetectionSources) -> Self {
+        let static_seconds = vec![None; bboxes.len()];
+        let sources = vec![source; bboxes.len()];
+        RawDetections {
+            bboxes,
+            classes_ids,
+            confidences,
+            static_seconds,
+            sources,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.bboxes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bboxes.is_empty()
+    }
+    pub fn push(&mut self, bbox: RectCV, class_id: usize, confidence: f32, static_seconds: Option<f32>, sources: DetectionSources) {
+        self.bboxes.push(bbox);
+        self.classes_ids.push(class_id);
+        self.confidences.push(confidence);
+        self.static_seconds.push(static_seconds);
+        self.sources.push(sources);
+    }
+}
 
 #[derive(Debug)]
 pub struct Detections {
     pub blobs: Vec<SimpleBlob>,
     pub class_names: Vec<String>,
     pub confidences: Vec<f32>,
+    pub static_seconds: Vec<Option<f32>>,
+    pub sources: Vec<DetectionSources>,
 }
 
-pub fn process_yolo_detections(nms_bboxes: &Vec<RectCV>, nms_classes_ids: Vec<usize>, nms_confidences: Vec<f32>, net_classes: &[String], target_classes: &HashSet<String>, dt: f32, scale_width: f32, scale_height: f32) -> Detections {
-    if (nms_bboxes.len() != nms_classes_ids.len()) || (nms_bboxes.len() != nms_confidences.len()) || (nms_classes_ids.len() != nms_confidences.len()) {
+pub fn process_yolo_detections(raw: &RawDetections, net_classes: &[String], target_classes: &HashSet<String>, class_filters: &ClassFilters, dt: f32, scale_width: f32, scale_height: f32) -> Detections {
+    let n = raw.bboxes.len();
+    if (raw.classes_ids.len() != n) || (raw.confidences.len() != n) || (raw.static_seconds.len() != n) || (raw.sources.len() != n) {
         // Something wrong?
-        println!("BBoxes len: {}, Classed IDs len: {}, Confidences len: {}", nms_bboxes.len(), nms_classes_ids.len(), nms_confidences.len());
+        println!("BBoxes len: {}, Classed IDs len: {}, Confidences len: {}, Static seconds len: {}, Sources len: {}", n, raw.classes_ids.len(), raw.confidences.len(), raw.static_seconds.len(), raw.sources.len());
         return Detections {
             blobs: vec![],
             class_names: vec![],
-            confidences: vec![]
+            confidences: vec![],
+            static_seconds: vec![],
+            sources: vec![]
         };
     }
     let mut aggregated_data = vec![];
-    let mut class_names: Vec<String> = Vec::with_capacity(nms_classes_ids.len());
-    for (i, bbox) in nms_bboxes.iter().enumerate() {
-        let class_id = nms_classes_ids[i];
+    let mut class_names: Vec<String> = Vec::with_capacity(n);
+    let mut confidences: Vec<f32> = Vec::with_capacity(n);
+    let mut static_seconds: Vec<Option<f32>> = Vec::with_capacity(n);
+    let mut sources: Vec<DetectionSources> = Vec::with_capacity(n);
+    for (i, bbox) in raw.bboxes.iter().enumerate() {
+        let class_id = raw.classes_ids[i];
         if class_id >= net_classes.len() {
             // Evade panic?
             continue
@@ -37,7 +158,13 @@ pub fn process_yolo_detections(nms_bboxes: &Vec<RectCV>, nms_classes_ids: Vec<us
         if !target_classes.is_empty() && !target_classes.contains(&classname) {
             continue;
         }
+        if !class_filters.allows(&classname, raw.confidences[i], bbox.width as f32 * scale_width, bbox.height as f32 * scale_height) {
+            continue;
+        }
         class_names.push(classname);
+        confidences.push(raw.confidences[i]);
+        static_seconds.push(raw.static_seconds[i]);
+        sources.push(raw.sources[i]);
         let center_x = (bbox.x as f32 + bbox.width as f32 / 2.0) * scale_width;
         let center_y = (bbox.y as f32 + bbox.height as f32 / 2.0) * scale_height;
         let kb: SimpleBlob = SimpleBlob::new_with_center_dt(Point::new(center_x, center_y), Rect::new(bbox.x as f32 * scale_width, bbox.y as f32 * scale_height, bbox.width as f32 * scale_width, bbox.height as f32 * scale_height), dt);
@@ -47,6 +174,8 @@ pub fn process_yolo_detections(nms_bboxes: &Vec<RectCV>, nms_classes_ids: Vec<us
     Detections {
         blobs: aggregated_data,
         class_names,
-        confidences: nms_confidences,
+        confidences,
+        static_seconds,
+        sources,
     }
 }
@@ -0,0 +1,46 @@
+use crate::detection::{iou, RawDetections};
+
+pub const DEFAULT_FUSION_IOU_THRESHOLD: f32 = 0.5;
+
+/// Merges detections made on the background image and on the live frame. Both are expected in the same coordinates.
+/// Pairs of the same class with IoU not less than threshold become single detection with the more confident bbox and both sources.
+/// Pairs are matched greedily starting from the highest IoU. Unmatched detections are kept as is
+pub fn fuse_detections(background: RawDetections, frame: RawDetections, iou_threshold: f32) -> RawDetections {
+    let mut candidates: Vec<(f32, usize, usize)> = vec![];
+    for i in 0..background.len() {
+        for j in 0..frame.len() {
+            if background.classes_ids[i] != frame.classes_ids[j] {
+                continue;
+            }
+            let overlap = iou(&background.bboxes[i], &frame.bboxes[j]);
+            if overlap >= iou_threshold {
+                candidates.push((overlap, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut background_matched = vec![false; background.len()];
+    let mut frame_matched = vec![false; frame.len()];
+    let mut fused = RawDetections::default();
+    for (_, i, j) in candidates {
+        if background_matched[i] || frame_matched[j] {
+            continue;
+        }
+        background_matched[i] = true;
+        frame_matched[j] = true;
+        let (bbox, confidence) = if background.confidences[i] >= frame.confidences[j] {
+            (background.bboxes[i], background.confidences[i])
+        } else {
+            (frame.bboxes[j], frame.confidences[j])
+        };
+        let static_seconds = background.static_seconds[i].or(frame.static_seconds[j]);
+        fused.push(bbox, background.classes_ids[i], confidence, static_seconds, background.sources[i].union(&frame.sources[j]));
+    }
+    for i in (0..background.len()).filter(|i| !background_matched[*i]) {
+        fused.push(background.bboxes[i], background.classes_ids[i], background.confidences[i], background.static_seconds[i], background.sources[i]);
+    }
+    for j in (0..frame.len()).filter(|j| !frame_matched[*j]) {
+        fused.push(frame.bboxes[j], frame.classes_ids[j], frame.confidences[j], frame.static_seconds[j], frame.sources[j]);
+    }
+    fused
+}
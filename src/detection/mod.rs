@@ -1,3 +1,6 @@
 mod detection;
+mod class_filters;
+mod nms;
+mod fusion;
 
-pub use self::detection::*;
+pub use self::{detection::*, class_filters::*, nms::*, fusion::*};
@@ -0,0 +1,32 @@
+use opencv::core::Rect as RectCV;
+
+/// Intersection over union of two boxes
+pub fn iou(a: &RectCV, b: &RectCV) -> f32 {
+    let x1 = a.x.max(b.x);
+    let y1 = a.y.max(b.y);
+    let x2 = (a.x + a.width).min(b.x + b.width);
+    let y2 = (a.y + a.height).min(b.y + b.height);
+    if x2 <= x1 || y2 <= y1 {
+        return 0.0;
+    }
+    let intersection = ((x2 - x1) * (y2 - y1)) as f32;
+    let union = (a.width * a.height + b.width * b.height) as f32 - intersection;
+    if union <= 0.0 {
+        return 0.0;
+    }
+    intersection / union
+}
+
+/// Greedy per-class non-maximum suppression. Returns indices of kept boxes in descending order of confidence
+pub fn nms(bboxes: &[RectCV], classes_ids: &[usize], confidences: &[f32], nms_threshold: f32) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..bboxes.len()).collect();
+    order.sort_by(|a, b| confidences[*b].total_cmp(&confidences[*a]));
+    let mut keep: Vec<usize> = vec![];
+    for idx in order {
+        let suppressed = keep.iter().any(|kept| classes_ids[*kept] == classes_ids[idx] && iou(&bboxes[*kept], &bboxes[idx]) > nms_threshold);
+        if !suppressed {
+            keep.push(idx);
+        }
+    }
+    keep
+}
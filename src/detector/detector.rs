@@ -0,0 +1,84 @@
+use opencv::core::{Mat, Rect};
+
+use std::str::FromStr;
+
+use crate::detector::DetectorError;
+
+/// Bounding boxes, classes identifiers and confidences of detected objects
+pub type DetectorOutput = (Vec<Rect>, Vec<usize>, Vec<f32>);
+
+/// Object detector used by the detection loop
+pub trait Detector {
+    /// Detects objects on the image. Boxes are in coordinates of the image.
+    /// Frame index is the number of frames of the camera processed before this one
+    fn detect(&mut self, image: &Mat, frame_index: u64, conf_threshold: f32, nms_threshold: f32) -> Result<DetectorOutput, DetectorError>;
+}
+
+/// Engine which runs the neural network
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetectorBackend {
+    /// OpenCV's DNN module on CUDA if any CUDA device is available, on CPU otherwise
+    Auto,
+    /// OpenCV's DNN module with its own implementation of layers
+    OpenCV,
+    /// OpenCV's DNN module with CUDA
+    Cuda,
+    /// OpenCV's DNN module with OpenVINO (Inference Engine)
+    OpenVINO,
+    /// ONNX Runtime (CPU execution provider). Requires "ort" feature
+    OnnxRuntime,
+}
+
+impl DetectorBackend {
+    /// Targets which could be used with the backend. First one is the default. Empty for automatic choice
+    pub fn supported_targets(&self) -> &'static [DetectorTarget] {
+        match self {
+            DetectorBackend::Auto => &[],
+            DetectorBackend::OpenCV => &[DetectorTarget::Cpu, DetectorTarget::OpenCL, DetectorTarget::OpenCLFP16],
+            DetectorBackend::Cuda => &[DetectorTarget::Cuda, DetectorTarget::CudaFP16],
+            DetectorBackend::OpenVINO => &[DetectorTarget::Cpu, DetectorTarget::OpenCL, DetectorTarget::OpenCLFP16, DetectorTarget::Myriad],
+            DetectorBackend::OnnxRuntime => &[DetectorTarget::Cpu],
+        }
+    }
+}
+
+impl FromStr for DetectorBackend {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(DetectorBackend::Auto),
+            "opencv" => Ok(DetectorBackend::OpenCV),
+            "cuda" => Ok(DetectorBackend::Cuda),
+            "openvino" | "inference_engine" => Ok(DetectorBackend::OpenVINO),
+            "onnxruntime" => Ok(DetectorBackend::OnnxRuntime),
+            _ => Err(format!("Bad detector backend: '{}'. Available: 'auto', 'opencv', 'cuda', 'openvino', 'onnxruntime'", s)),
+        }
+    }
+}
+
+/// Device (and precision) the neural network is computed on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetectorTarget {
+    Cpu,
+    OpenCL,
+    OpenCLFP16,
+    Cuda,
+    CudaFP16,
+    /// Intel Neural Compute Stick (OpenVINO only)
+    Myriad,
+}
+
+impl FromStr for DetectorTarget {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cpu" => Ok(DetectorTarget::Cpu),
+            "opencl" => Ok(DetectorTarget::OpenCL),
+            "opencl_fp16" => Ok(DetectorTarget::OpenCLFP16),
+            "cuda" => Ok(DetectorTarget::Cuda),
+            "cuda_fp16" => Ok(DetectorTarget::CudaFP16),
+            "myriad" => Ok(DetectorTarget::Myriad),
+            _ => Err(format!("Bad detector target: '{}'. Available: 'cpu', 'opencl', 'opencl_fp16', 'cuda', 'cuda_fp16', 'myriad'", s)),
+        }
+    }
+}
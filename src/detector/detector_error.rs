@@ -0,0 +1,50 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum DetectorError {
+    OpenCVError(opencv::Error),
+    IOError(std::io::Error),
+    JSONError(serde_json::Error),
+    #[cfg(feature = "ort")]
+    ORTError(ort::Error),
+    /// Malformed file of scripted detections
+    ParseError(String),
+}
+
+impl fmt::Display for DetectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DetectorError::OpenCVError(e) => write!(f, "{}", e),
+            DetectorError::IOError(e) => write!(f, "{}", e),
+            DetectorError::JSONError(e) => write!(f, "{}", e),
+            #[cfg(feature = "ort")]
+            DetectorError::ORTError(e) => write!(f, "{}", e),
+            DetectorError::ParseError(e) => write!(f, "Can't parse detections: {}", e),
+        }
+    }
+}
+
+impl From<opencv::Error> for DetectorError {
+    fn from(e: opencv::Error) -> Self {
+        DetectorError::OpenCVError(e)
+    }
+}
+
+impl From<std::io::Error> for DetectorError {
+    fn from(e: std::io::Error) -> Self {
+        DetectorError::IOError(e)
+    }
+}
+
+impl From<serde_json::Error> for DetectorError {
+    fn from(e: serde_json::Error) -> Self {
+        DetectorError::JSONError(e)
+    }
+}
+
+#[cfg(feature = "ort")]
+impl From<ort::Error> for DetectorError {
+    fn from(e: ort::Error) -> Self {
+        DetectorError::ORTError(e)
+    }
+}
@@ -0,0 +1,90 @@
+use opencv::core::{Mat, Rect};
+use serde::Deserialize;
+
+use crate::detector::{Detector, DetectorError, DetectorOutput};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Single scripted detection. Bbox is [x, y, width, height] in coordinates of the image passed to the detector
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockDetection {
+    pub frame: u64,
+    pub bbox: [i32; 4],
+    pub class_id: usize,
+    pub confidence: f32,
+}
+
+/// Replays detections from the file instead of running neural network. Useful for testing tracker, zones and events without model weights.
+/// JSON file is an array of `MockDetection`. CSV file has header `frame,x,y,width,height,class_id,confidence`
+pub struct MockDetector {
+    detections: HashMap<u64, Vec<MockDetection>>,
+}
+
+impl MockDetector {
+    pub fn new(detections: Vec<MockDetection>) -> Self {
+        let mut by_frame: HashMap<u64, Vec<MockDetection>> = HashMap::new();
+        for detection in detections {
+            by_frame.entry(detection.frame).or_default().push(detection);
+        }
+        MockDetector { detections: by_frame }
+    }
+    /// Loads detections from CSV (by extension) or JSON file
+    pub fn new_from_file(filename: &str) -> Result<Self, DetectorError> {
+        let contents = fs::read_to_string(filename)?;
+        let is_csv = Path::new(filename).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let detections = if is_csv {
+            parse_csv(&contents)?
+        } else {
+            serde_json::from_str::<Vec<MockDetection>>(&contents)?
+        };
+        Ok(MockDetector::new(detections))
+    }
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<MockDetection>, DetectorError> {
+    let mut detections = vec![];
+    // First line is header
+    for (line_idx, line) in contents.lines().enumerate().skip(1) {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() != 7 {
+            return Err(DetectorError::ParseError(format!("line {}: expected 7 fields, got {}", line_idx + 1, fields.len())));
+        }
+        let bad_field = |name: &str| DetectorError::ParseError(format!("line {}: bad {}", line_idx + 1, name));
+        detections.push(MockDetection {
+            frame: fields[0].parse().map_err(|_| bad_field("frame"))?,
+            bbox: [
+                fields[1].parse().map_err(|_| bad_field("x"))?,
+                fields[2].parse().map_err(|_| bad_field("y"))?,
+                fields[3].parse().map_err(|_| bad_field("width"))?,
+                fields[4].parse().map_err(|_| bad_field("height"))?,
+            ],
+            class_id: fields[5].parse().map_err(|_| bad_field("class_id"))?,
+            confidence: fields[6].parse().map_err(|_| bad_field("confidence"))?,
+        });
+    }
+    Ok(detections)
+}
+
+impl Detector for MockDetector {
+    /// Returns detections scripted for the frame. NMS is not applied: scripted boxes are expected to be final
+    fn detect(&mut self, _image: &Mat, frame_index: u64, conf_threshold: f32, _nms_threshold: f32) -> Result<DetectorOutput, DetectorError> {
+        let mut bboxes = vec![];
+        let mut classes_ids = vec![];
+        let mut confidences = vec![];
+        for detection in self.detections.get(&frame_index).iter().flat_map(|detections| detections.iter()) {
+            if detection.confidence < conf_threshold {
+                continue;
+            }
+            bboxes.push(Rect::new(detection.bbox[0], detection.bbox[1], detection.bbox[2], detection.bbox[3]));
+            classes_ids.push(detection.class_id);
+            confidences.push(detection.confidence);
+        }
+        Ok((bboxes, classes_ids, confidences))
+    }
+}
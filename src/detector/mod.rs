@@ -0,0 +1,11 @@
+mod detector_error;
+mod detector;
+mod opencv_detector;
+mod mock_detector;
+#[cfg(feature = "ort")]
+mod ort_detector;
+mod tiling;
+
+pub use self::{detector_error::*, detector::*, opencv_detector::*, mock_detector::*, tiling::*};
+#[cfg(feature = "ort")]
+pub use self::ort_detector::*;
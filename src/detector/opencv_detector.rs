@@ -0,0 +1,22 @@
+use opencv::core::Mat;
+use od_opencv::model::ModelTrait;
+
+use crate::detector::{Detector, DetectorError, DetectorOutput};
+
+/// YOLO model running via OpenCV's DNN module
+pub struct OpenCVDetector {
+    model: Box<dyn ModelTrait>,
+}
+
+impl OpenCVDetector {
+    pub fn new(model: Box<dyn ModelTrait>) -> Self {
+        OpenCVDetector { model }
+    }
+}
+
+impl Detector for OpenCVDetector {
+    fn detect(&mut self, image: &Mat, _frame_index: u64, conf_threshold: f32, nms_threshold: f32) -> Result<DetectorOutput, DetectorError> {
+        let output = self.model.forward(image, conf_threshold, nms_threshold)?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,127 @@
+use opencv::{
+    core::{copy_make_border, Mat, Rect, Scalar, Size, BORDER_CONSTANT},
+    imgproc::{resize, INTER_LINEAR},
+    prelude::{MatTraitConst, MatTraitConstManual},
+};
+use ndarray::Ix3;
+use ort::{
+    execution_providers::CPUExecutionProvider,
+    session::{builder::GraphOptimizationLevel, Session},
+    value::Tensor,
+};
+
+use crate::detection::nms;
+use crate::detector::{Detector, DetectorError, DetectorOutput};
+
+// Color of letterbox padding (same as used by YOLO training pipelines)
+const PADDING_COLOR: f64 = 114.0;
+
+/// Parameters of letterbox transformation: image is scaled keeping aspect ratio and padded to the network input size
+struct Letterbox {
+    scale: f32,
+    pad_x: f32,
+    pad_y: f32,
+}
+
+/// YOLO model exported to ONNX running via ONNX Runtime on CPU.
+/// Both YOLOv5/v7-style output [1, N, 5 + classes] and YOLOv8-style output [1, 4 + classes, N] are supported
+pub struct OrtDetector {
+    session: Session,
+    input_width: i32,
+    input_height: i32,
+    classes_number: usize,
+}
+
+impl OrtDetector {
+    pub fn new_from_file(weights: &str, input_size: (i32, i32), classes_number: usize) -> Result<Self, DetectorError> {
+        let session = Session::builder()?
+            .with_execution_providers([CPUExecutionProvider::default().build()])?
+            .with_optimization_level(GraphOptimizationLevel::Level3)?
+            .commit_from_file(weights)?;
+        Ok(OrtDetector {
+            session,
+            input_width: input_size.0,
+            input_height: input_size.1,
+            classes_number,
+        })
+    }
+    /// Letterboxes BGR image and converts it to normalized RGB tensor data in NCHW layout
+    fn preprocess(&self, image: &Mat) -> Result<(Vec<f32>, Letterbox), DetectorError> {
+        let image_size = image.size()?;
+        let scale = (self.input_width as f32 / image_size.width as f32).min(self.input_height as f32 / image_size.height as f32);
+        let scaled_width = ((image_size.width as f32 * scale).round() as i32).clamp(1, self.input_width);
+        let scaled_height = ((image_size.height as f32 * scale).round() as i32).clamp(1, self.input_height);
+        let mut scaled = Mat::default();
+        resize(image, &mut scaled, Size::new(scaled_width, scaled_height), 0.0, 0.0, INTER_LINEAR)?;
+        let pad_x = (self.input_width - scaled_width) / 2;
+        let pad_y = (self.input_height - scaled_height) / 2;
+        let mut padded = Mat::default();
+        copy_make_border(&scaled, &mut padded, pad_y, self.input_height - scaled_height - pad_y, pad_x, self.input_width - scaled_width - pad_x, BORDER_CONSTANT, Scalar::all(PADDING_COLOR))?;
+        let bytes = padded.data_bytes()?;
+        let plane = (self.input_width * self.input_height) as usize;
+        if bytes.len() != plane * 3 {
+            return Err(DetectorError::ParseError(format!("expected 3-channel 8-bit image, got {} bytes for {}x{}", bytes.len(), self.input_width, self.input_height)));
+        }
+        let mut data = vec![0.0_f32; plane * 3];
+        for (idx, pixel) in bytes.chunks_exact(3).enumerate() {
+            // BGR -> RGB
+            data[idx] = pixel[2] as f32 / 255.0;
+            data[plane + idx] = pixel[1] as f32 / 255.0;
+            data[2 * plane + idx] = pixel[0] as f32 / 255.0;
+        }
+        Ok((data, Letterbox { scale, pad_x: pad_x as f32, pad_y: pad_y as f32 }))
+    }
+}
+
+impl Detector for OrtDetector {
+    fn detect(&mut self, image: &Mat, _frame_index: u64, conf_threshold: f32, nms_threshold: f32) -> Result<DetectorOutput, DetectorError> {
+        let (data, letterbox) = self.preprocess(image)?;
+        let input = Tensor::<f32>::from_array(([1_usize, 3, self.input_height as usize, self.input_width as usize], data))?;
+        let outputs = self.session.run(ort::inputs![input]?)?;
+        let output = outputs[0].try_extract_tensor::<f32>()?.into_dimensionality::<Ix3>().map_err(|e| DetectorError::ParseError(format!("unexpected output shape: {}", e)))?;
+        let shape = output.shape();
+        // YOLOv5/v7 layout is checked first: its number of candidates could be equal to 4 + classes by chance, while YOLOv8 one is always much bigger than 5 + classes.
+        // YOLOv8 keeps attributes of candidates along the 2nd axis and has no objectness score
+        let (candidates, with_objectness, transposed) = if shape[2] == 5 + self.classes_number {
+            (shape[1], true, false)
+        } else if shape[1] == 4 + self.classes_number {
+            (shape[2], false, true)
+        } else {
+            return Err(DetectorError::ParseError(format!("output shape {:?} does not match {} classes", shape, self.classes_number)));
+        };
+        let attribute = |candidate: usize, idx: usize| -> f32 {
+            if transposed { output[[0, idx, candidate]] } else { output[[0, candidate, idx]] }
+        };
+        let classes_offset = if with_objectness { 5 } else { 4 };
+        let mut bboxes = vec![];
+        let mut classes_ids = vec![];
+        let mut confidences = vec![];
+        for candidate in 0..candidates {
+            let objectness = if with_objectness { attribute(candidate, 4) } else { 1.0 };
+            if objectness < conf_threshold {
+                continue;
+            }
+            let (class_id, class_score) = (0..self.classes_number)
+                .map(|class_id| (class_id, attribute(candidate, classes_offset + class_id)))
+                .fold((0, f32::MIN), |best, current| if current.1 > best.1 { current } else { best });
+            let confidence = objectness * class_score;
+            if confidence < conf_threshold {
+                continue;
+            }
+            // Center, width and height in letterboxed coordinates -> left top, width and height in coordinates of the source image
+            let width = attribute(candidate, 2) / letterbox.scale;
+            let height = attribute(candidate, 3) / letterbox.scale;
+            let x = (attribute(candidate, 0) - letterbox.pad_x) / letterbox.scale - width / 2.0;
+            let y = (attribute(candidate, 1) - letterbox.pad_y) / letterbox.scale - height / 2.0;
+            bboxes.push(Rect::new(x.round() as i32, y.round() as i32, width.round() as i32, height.round() as i32));
+            classes_ids.push(class_id);
+            confidences.push(confidence);
+        }
+        let keep = nms(&bboxes, &classes_ids, &confidences, nms_threshold);
+        Ok((
+            keep.iter().map(|idx| bboxes[*idx]).collect(),
+            keep.iter().map(|idx| classes_ids[*idx]).collect(),
+            keep.iter().map(|idx| confidences[*idx]).collect(),
+        ))
+    }
+}
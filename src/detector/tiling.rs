@@ -0,0 +1,160 @@
+use opencv::core::{Mat, Rect};
+use opencv::prelude::MatTraitConst;
+
+use crate::detection::nms;
+use crate::detector::{Detector, DetectorError, DetectorOutput};
+
+use std::str::FromStr;
+
+pub const DEFAULT_TILE_OVERLAP: f32 = 0.2;
+
+/// Parts of the frame covered by tiles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TilingAreas {
+    /// Whole frame
+    Frame,
+    /// Bounding rectangle of every zone
+    Zones,
+}
+
+impl FromStr for TilingAreas {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "frame" => Ok(TilingAreas::Frame),
+            "zones" => Ok(TilingAreas::Zones),
+            _ => Err(format!("Bad tiling areas: '{}'. Available: 'frame', 'zones'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilingParams {
+    pub tile_width: i32,
+    pub tile_height: i32,
+    /// Share of the tile overlapped by its neighbour in range [0; 1)
+    pub overlap: f32,
+    pub areas: TilingAreas,
+    /// Run detector on the whole image too, so objects larger than tile are not lost
+    pub include_full_image: bool,
+}
+
+/// Start positions of tiles along single axis. Last tile is aligned to the end of the segment
+fn tile_offsets(start: i32, length: i32, tile: i32, step: i32) -> Vec<i32> {
+    if length <= tile {
+        return vec![start];
+    }
+    let mut offsets: Vec<i32> = (0..).map(|idx| start + idx * step).take_while(|offset| offset + tile < start + length).collect();
+    offsets.push(start + length - tile);
+    offsets
+}
+
+/// Splits the area into overlapping tiles. Tiles do not exceed the area, so they are smaller than requested for small areas
+pub fn make_tiles(area: Rect, params: &TilingParams) -> Vec<Rect> {
+    if area.width <= 0 || area.height <= 0 {
+        return vec![];
+    }
+    let tile_width = params.tile_width.min(area.width);
+    let tile_height = params.tile_height.min(area.height);
+    let step_x = ((tile_width as f32 * (1.0 - params.overlap)) as i32).max(1);
+    let step_y = ((tile_height as f32 * (1.0 - params.overlap)) as i32).max(1);
+    let mut tiles = vec![];
+    for y in tile_offsets(area.y, area.height, tile_height, step_y) {
+        for x in tile_offsets(area.x, area.width, tile_width, step_x) {
+            tiles.push(Rect::new(x, y, tile_width, tile_height));
+        }
+    }
+    tiles
+}
+
+/// Runs detector on every tile (and optionally on the whole image), maps boxes back to coordinates of the image
+/// and merges duplicates on tiles borders via NMS
+pub fn detect_tiled(detector: &mut dyn Detector, image: &Mat, tiles: &[Rect], include_full_image: bool, frame_index: u64, conf_threshold: f32, nms_threshold: f32) -> Result<DetectorOutput, DetectorError> {
+    let mut bboxes = vec![];
+    let mut classes_ids = vec![];
+    let mut confidences = vec![];
+    if include_full_image {
+        let (full_bboxes, full_classes_ids, full_confidences) = detector.detect(image, frame_index, conf_threshold, nms_threshold)?;
+        bboxes.extend(full_bboxes);
+        classes_ids.extend(full_classes_ids);
+        confidences.extend(full_confidences);
+    }
+    let image_size = image.size()?;
+    for tile in tiles.iter() {
+        // Tiles are prepared for the frame size known at the moment, so clip them just in case
+        let x = tile.x.max(0);
+        let y = tile.y.max(0);
+        let width = (tile.x + tile.width).min(image_size.width) - x;
+        let height = (tile.y + tile.height).min(image_size.height) - y;
+        if width <= 0 || height <= 0 {
+            continue;
+        }
+        let tile_image = Mat::roi(image, Rect::new(x, y, width, height))?.try_clone()?;
+        let (tile_bboxes, tile_classes_ids, tile_confidences) = detector.detect(&tile_image, frame_index, conf_threshold, nms_threshold)?;
+        bboxes.extend(tile_bboxes.into_iter().map(|bbox| Rect::new(bbox.x + x, bbox.y + y, bbox.width, bbox.height)));
+        classes_ids.extend(tile_classes_ids);
+        confidences.extend(tile_confidences);
+    }
+    let keep = nms(&bboxes, &classes_ids, &confidences, nms_threshold);
+    Ok((
+        keep.iter().map(|idx| bboxes[*idx]).collect(),
+        keep.iter().map(|idx| classes_ids[*idx]).collect(),
+        keep.iter().map(|idx| confidences[*idx]).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::{MockDetection, MockDetector};
+
+    use opencv::core::{Scalar, CV_8UC3};
+
+    fn params(tile: i32, overlap: f32) -> TilingParams {
+        TilingParams { tile_width: tile, tile_height: tile, overlap, areas: TilingAreas::Frame, include_full_image: false }
+    }
+
+    #[test]
+    fn area_smaller_than_tile_is_covered_by_single_tile() {
+        let area = Rect::new(10, 20, 100, 50);
+        assert_eq!(make_tiles(area, &params(608, 0.2)), vec![area]);
+    }
+
+    #[test]
+    fn last_tile_is_aligned_to_the_edge() {
+        assert_eq!(tile_offsets(0, 1000, 400, 320), vec![0, 320, 600]);
+        assert_eq!(tile_offsets(50, 800, 400, 400), vec![50, 450]);
+        let tiles = make_tiles(Rect::new(0, 0, 1000, 500), &params(400, 0.2));
+        assert_eq!(tiles.len(), 3 * 2);
+        assert!(tiles.iter().all(|tile| tile.width == 400 && tile.height == 400 && tile.x + tile.width <= 1000 && tile.y + tile.height <= 500));
+        assert_eq!(tiles.last().unwrap(), &Rect::new(600, 100, 400, 400));
+    }
+
+    #[test]
+    fn step_is_at_least_one_pixel() {
+        // Tile of 1 pixel with overlap gives zero step without clamping
+        let tiles = make_tiles(Rect::new(0, 0, 5, 1), &params(1, 0.9));
+        assert_eq!(tiles.iter().map(|tile| tile.x).collect::<Vec<i32>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn boxes_are_shifted_by_tile_origin_and_merged_across_borders() {
+        let image = Mat::new_rows_cols_with_default(100, 200, CV_8UC3, Scalar::all(0.0)).unwrap();
+        // Mock ignores the image, so every tile gets both boxes in its own coordinates
+        let mut detector = MockDetector::new(vec![
+            MockDetection { frame: 0, bbox: [10, 10, 20, 20], class_id: 0, confidence: 0.9 },
+            MockDetection { frame: 0, bbox: [90, 10, 20, 20], class_id: 0, confidence: 0.8 },
+        ]);
+        let tiles = [Rect::new(0, 0, 100, 100), Rect::new(80, 0, 100, 100)];
+        let (bboxes, classes_ids, confidences) = detect_tiled(&mut detector, &image, &tiles, false, 0, 0.5, 0.4).unwrap();
+        let mut detections: Vec<(Rect, f32)> = bboxes.into_iter().zip(confidences).collect();
+        detections.sort_by_key(|(bbox, _)| bbox.x);
+        // Second box of the first tile and first box of the second one are the same object on the border
+        assert_eq!(detections, vec![
+            (Rect::new(10, 10, 20, 20), 0.9),
+            (Rect::new(90, 10, 20, 20), 0.9),
+            (Rect::new(170, 10, 20, 20), 0.8),
+        ]);
+        assert_eq!(classes_ids, vec![0, 0, 0]);
+    }
+}
@@ -1,10 +1,11 @@
-use crate::utils::serialize_mat_as_base64;
+use crate::utils::{serialize_mat_as_base64, deserialize_base64_as_mat};
 
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use opencv::core::Mat;
+use crate::detection::DetectionSources;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct EventBBox {
     pub x: i32,
     pub y: i32,
@@ -12,17 +13,63 @@ pub struct EventBBox {
     pub height: i32
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct EventPOI {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Debug, Serialize)]
+/// Discriminator of published messages, since anomaly and system events share channels of publishers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// Incident in the zone
+    #[default]
+    Anomaly,
+    /// State of the application itself (e.g. watchdog alerts)
+    System,
+}
+
+/// Stage of the incident lifecycle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    /// Object has been registered in the zone
+    #[default]
+    EventStarted,
+    /// Periodic heartbeat with current state of the object
+    EventUpdated,
+    /// Object has disappeared (tracker has forgotten it), left the zone or exceeded max lifetime
+    EventEnded,
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::EventStarted => "event_started",
+            EventType::EventUpdated => "event_updated",
+            EventType::EventEnded => "event_ended",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct EventInfo {
+    /// Missing in events stored before system events have been introduced
+    #[serde(default)]
+    kind: EventKind,
     id: Uuid,
+    /// Missing in events stored before incident lifecycle support. Every such event has been registration one
+    #[serde(default)]
+    event_type: EventType,
+    /// Shared by every event of the same incident. Nil for events stored before incident lifecycle support
+    #[serde(default)]
+    incident_id: Uuid,
     event_registered_at: i64,
-    #[serde(serialize_with = "serialize_mat_as_base64")]
+    /// Offset from the beginning of the stream. Missing in events stored before it has been introduced
+    #[serde(default)]
+    event_media_offset_ms: i64,
+    #[serde(serialize_with = "serialize_mat_as_base64", deserialize_with = "deserialize_base64_as_mat")]
     event_image: Option<Mat>,
     object_id: String,
     object_registered_at: i64,
@@ -31,15 +78,29 @@ pub struct EventInfo {
     object_poi: EventPOI,
     object_classname: String,
     object_confidence: f32,
+    /// How long (in seconds) the region of the object has been static. Present only when static foreground detection is enabled
+    #[serde(default)]
+    object_static_seconds: Option<f32>,
+    /// Images the object has been detected on
+    #[serde(default)]
+    object_sources: DetectionSources,
     zone_id: String,
+    /// Missing in events stored before multi-camera support
+    #[serde(default)]
+    camera_id: String,
     equipment_id: Option<String>
 }
 
 impl EventInfo{
     pub fn new(unix_tm: i64, frame: Option<&Mat>, object_id: String, object_registered_unix_tm: i64, object_lifetime: i64, object_bbox: EventBBox, object_poi: EventPOI, classname: String, confidence: f32, zone_id: String, equipment_id: Option<String>) -> Self {
+        let id = Uuid::new_v4();
         EventInfo{
-            id: Uuid::new_v4(),
+            kind: EventKind::Anomaly,
+            id,
+            event_type: EventType::EventStarted,
+            incident_id: id,
             event_registered_at: unix_tm,
+            event_media_offset_ms: 0,
             // event_image: frame.map(|img| img.clone()),
             event_image: frame.cloned(),
             object_id,
@@ -49,14 +110,57 @@ impl EventInfo{
             object_poi,
             object_classname: classname,
             object_confidence: confidence,
+            object_static_seconds: None,
+            object_sources: DetectionSources::default(),
             zone_id,
+            camera_id: String::new(),
             equipment_id 
         }
     }
+    /// Binds event to the incident
+    pub fn with_incident(mut self, event_type: EventType, incident_id: Uuid) -> Self {
+        self.event_type = event_type;
+        self.incident_id = incident_id;
+        self
+    }
+    pub fn with_media_offset(mut self, media_offset_ms: i64) -> Self {
+        self.event_media_offset_ms = media_offset_ms;
+        self
+    }
+    pub fn with_camera_id(mut self, camera_id: String) -> Self {
+        self.camera_id = camera_id;
+        self
+    }
+    pub fn with_static_seconds(mut self, static_seconds: Option<f32>) -> Self {
+        self.object_static_seconds = static_seconds;
+        self
+    }
+    pub fn with_sources(mut self, sources: DetectionSources) -> Self {
+        self.object_sources = sources;
+        self
+    }
     pub fn get_id(&self) -> Uuid {
         self.id
     }
+    pub fn get_event_type(&self) -> EventType {
+        self.event_type
+    }
+    pub fn get_incident_id(&self) -> Uuid {
+        self.incident_id
+    }
     pub fn get_object_id(&self) -> String {
         self.object_id.clone()
     }
+    pub fn get_classname(&self) -> String {
+        self.object_classname.clone()
+    }
+    pub fn get_zone_id(&self) -> String {
+        self.zone_id.clone()
+    }
+    pub fn get_camera_id(&self) -> String {
+        self.camera_id.clone()
+    }
+    pub fn get_equipment_id(&self) -> Option<String> {
+        self.equipment_id.clone()
+    }
 }
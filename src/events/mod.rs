@@ -1,3 +1,4 @@
 pub mod events;
+pub mod system_events;
 
-pub use self::events::*;
+pub use self::{events::*, system_events::*};
@@ -0,0 +1,80 @@
+use crate::events::EventKind;
+
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+/// Abnormal condition of the application detected by the watchdog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemAlert {
+    /// No frames have been read from the video source for too long
+    StreamStalled,
+    /// Video source keeps returning identical frames
+    FrameFrozen,
+    /// Frames are read but detection loop does not process them
+    DetectionStalled,
+    /// Video source has failed and is being reopened
+    SourceLost,
+}
+
+impl SystemAlert {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemAlert::StreamStalled => "stream_stalled",
+            SystemAlert::FrameFrozen => "frame_frozen",
+            SystemAlert::DetectionStalled => "detection_stalled",
+            SystemAlert::SourceLost => "source_lost",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemEventType {
+    AlertRaised,
+    AlertCleared,
+    /// Video source has been reopened after failure
+    SourceReconnected,
+}
+
+/// Event about the state of the application itself. It is not related to any zone or object
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SystemEvent {
+    /// Always "system"
+    kind: EventKind,
+    id: Uuid,
+    event_type: SystemEventType,
+    alert: SystemAlert,
+    message: String,
+    event_registered_at: i64,
+    equipment_id: Option<String>,
+    /// Video source the event relates to. Empty for application-wide events (e.g. stalled detection)
+    camera_id: Option<String>,
+}
+
+impl SystemEvent {
+    pub fn new(unix_tm: i64, event_type: SystemEventType, alert: SystemAlert, message: String, equipment_id: Option<String>, camera_id: Option<String>) -> Self {
+        SystemEvent {
+            kind: EventKind::System,
+            id: Uuid::new_v4(),
+            event_type,
+            alert,
+            message,
+            event_registered_at: unix_tm,
+            equipment_id,
+            camera_id,
+        }
+    }
+    pub fn get_id(&self) -> Uuid {
+        self.id
+    }
+    pub fn get_alert(&self) -> SystemAlert {
+        self.alert
+    }
+    pub fn get_equipment_id(&self) -> Option<String> {
+        self.equipment_id.clone()
+    }
+    pub fn get_camera_id(&self) -> Option<String> {
+        self.camera_id.clone()
+    }
+}
@@ -0,0 +1,181 @@
+use crate::events::SystemAlert;
+
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+
+static HEALTH: OnceLock<HealthState> = OnceLock::new();
+
+/// Result of the last publishing attempt
+#[derive(Serialize, Debug, Clone)]
+pub struct PublisherHealth {
+    pub connected: bool,
+    pub last_error: Option<String>,
+    pub updated_at: i64,
+}
+
+/// Alert with the camera it relates to. Alerts which are not related to any camera have no camera identifier
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ActiveAlert {
+    pub alert: SystemAlert,
+    pub camera_id: Option<String>,
+}
+
+/// State of the single video source
+#[derive(Serialize, Debug, Clone)]
+pub struct CameraHealth {
+    pub capture_alive: bool,
+    pub last_frame_age_ms: Option<i64>,
+    /// Number of consecutive identical frames
+    pub identical_frames: u64,
+    pub capture_stop_reason: Option<String>,
+}
+
+/// Snapshot of the application state returned by /healthz and /readyz
+#[derive(Serialize, Debug)]
+pub struct HealthReport {
+    /// Every capture and detection are running and there are no watchdog alerts
+    pub healthy: bool,
+    /// Healthy, model is loaded, at least one frame has been processed and every publisher is reachable
+    pub ready: bool,
+    pub last_processed_age_ms: Option<i64>,
+    pub model_loaded: bool,
+    pub detection_alive: bool,
+    pub cameras: BTreeMap<String, CameraHealth>,
+    pub publishers: BTreeMap<String, PublisherHealth>,
+    pub alerts: Vec<ActiveAlert>,
+}
+
+#[derive(Default)]
+struct CameraState {
+    capture_alive: bool,
+    /// Unix milliseconds. Zero means "never"
+    last_frame_ms: i64,
+    identical_frames: u64,
+    capture_stop_reason: Option<String>,
+}
+
+/// State of the application shared between capture threads, detection loop, publishers and HTTP server
+pub struct HealthState {
+    /// Unix milliseconds. Zero means "never"
+    last_processed_ms: AtomicI64,
+    model_loaded: AtomicBool,
+    detection_alive: AtomicBool,
+    cameras: Mutex<BTreeMap<String, CameraState>>,
+    publishers: Mutex<BTreeMap<String, PublisherHealth>>,
+    alerts: Mutex<Vec<ActiveAlert>>,
+}
+
+impl HealthState {
+    fn new() -> Self {
+        HealthState {
+            last_processed_ms: AtomicI64::new(0),
+            model_loaded: AtomicBool::new(false),
+            detection_alive: AtomicBool::new(false),
+            cameras: Mutex::new(BTreeMap::new()),
+            publishers: Mutex::new(BTreeMap::new()),
+            alerts: Mutex::new(vec![]),
+        }
+    }
+    pub fn set_model_loaded(&self, loaded: bool) {
+        self.model_loaded.store(loaded, Ordering::Release);
+    }
+    pub fn capture_started(&self, camera_id: &str) {
+        let mut cameras = self.cameras.lock().unwrap_or_else(|e| e.into_inner());
+        let camera = cameras.entry(camera_id.to_string()).or_default();
+        camera.capture_alive = true;
+        camera.capture_stop_reason = None;
+    }
+    pub fn capture_stopped(&self, camera_id: &str, reason: &str) {
+        let mut cameras = self.cameras.lock().unwrap_or_else(|e| e.into_inner());
+        let camera = cameras.entry(camera_id.to_string()).or_default();
+        camera.capture_alive = false;
+        camera.capture_stop_reason = Some(reason.to_string());
+    }
+    pub fn get_capture_stop_reason(&self, camera_id: &str) -> Option<String> {
+        self.cameras.lock().unwrap_or_else(|e| e.into_inner()).get(camera_id).and_then(|camera| camera.capture_stop_reason.clone())
+    }
+    pub fn set_detection_alive(&self, alive: bool) {
+        self.detection_alive.store(alive, Ordering::Release);
+    }
+    /// Registers frame read from the video source
+    pub fn frame_read(&self, camera_id: &str, identical_to_previous: bool) {
+        let mut cameras = self.cameras.lock().unwrap_or_else(|e| e.into_inner());
+        let camera = cameras.entry(camera_id.to_string()).or_default();
+        camera.last_frame_ms = now_ms();
+        if identical_to_previous {
+            camera.identical_frames += 1;
+        } else {
+            camera.identical_frames = 0;
+        }
+    }
+    /// Registers frame processed by the detection loop
+    pub fn frame_processed(&self) {
+        self.last_processed_ms.store(now_ms(), Ordering::Release);
+    }
+    pub fn set_publisher_result(&self, name: &str, error: Option<String>) {
+        let mut publishers = self.publishers.lock().unwrap_or_else(|e| e.into_inner());
+        publishers.insert(name.to_string(), PublisherHealth {
+            connected: error.is_none(),
+            last_error: error,
+            updated_at: now_ms() / 1000,
+        });
+    }
+    pub fn set_alerts(&self, alerts: Vec<ActiveAlert>) {
+        *self.alerts.lock().unwrap_or_else(|e| e.into_inner()) = alerts;
+    }
+    /// Milliseconds since the last frame has been processed
+    pub fn last_processed_age_ms(&self) -> Option<i64> {
+        age_ms(self.last_processed_ms.load(Ordering::Acquire))
+    }
+    /// State of every video source which has been started
+    pub fn cameras(&self) -> BTreeMap<String, CameraHealth> {
+        self.cameras.lock().unwrap_or_else(|e| e.into_inner()).iter().map(|(camera_id, camera)| {
+            (camera_id.clone(), CameraHealth {
+                capture_alive: camera.capture_alive,
+                last_frame_age_ms: age_ms(camera.last_frame_ms),
+                identical_frames: camera.identical_frames,
+                capture_stop_reason: camera.capture_stop_reason.clone(),
+            })
+        }).collect()
+    }
+    pub fn report(&self) -> HealthReport {
+        let alerts = self.alerts.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let publishers = self.publishers.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let cameras = self.cameras();
+        let model_loaded = self.model_loaded.load(Ordering::Acquire);
+        let detection_alive = self.detection_alive.load(Ordering::Acquire);
+        let last_processed_age_ms = self.last_processed_age_ms();
+        let healthy = !cameras.is_empty() && cameras.values().all(|c| c.capture_alive) && detection_alive && alerts.is_empty();
+        let ready = healthy && model_loaded && last_processed_age_ms.is_some() && publishers.values().all(|p| p.connected);
+        HealthReport {
+            healthy,
+            ready,
+            last_processed_age_ms,
+            model_loaded,
+            detection_alive,
+            cameras,
+            publishers,
+            alerts,
+        }
+    }
+}
+
+/// Global health state
+pub fn health() -> &'static HealthState {
+    HEALTH.get_or_init(HealthState::new)
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn age_ms(since_ms: i64) -> Option<i64> {
+    if since_ms == 0 {
+        None
+    } else {
+        Some((now_ms() - since_ms).max(0))
+    }
+}
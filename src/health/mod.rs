@@ -0,0 +1,4 @@
+mod health;
+mod watchdog;
+
+pub use self::{health::*, watchdog::*};
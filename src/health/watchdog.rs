@@ -0,0 +1,88 @@
+use crate::health::{health, HealthState, ActiveAlert};
+use crate::events::{SystemAlert, SystemEventType};
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Periodically checks health state for stalled streams, frozen frames and stalled detection
+pub struct Watchdog {
+    stream_stall_ms: i64,
+    detection_stall_ms: i64,
+    /// Zero disables the check
+    frozen_frames_limit: u64,
+}
+
+impl Watchdog {
+    pub fn new(stream_stall_seconds: u64, detection_stall_seconds: u64, frozen_frames_limit: u64) -> Self {
+        Watchdog {
+            stream_stall_ms: stream_stall_seconds as i64 * 1000,
+            detection_stall_ms: detection_stall_seconds as i64 * 1000,
+            frozen_frames_limit,
+        }
+    }
+    /// Alerts which are active at the moment with human-readable descriptions
+    pub fn check(&self, state: &HealthState) -> Vec<(ActiveAlert, String)> {
+        let mut alerts = vec![];
+        let mut any_stream_alive = false;
+        for (camera_id, camera) in state.cameras() {
+            let stream_stalled = match camera.last_frame_age_ms {
+                Some(age_ms) => age_ms > self.stream_stall_ms,
+                // Stream could not provide even a single frame
+                None => !camera.capture_alive && camera.capture_stop_reason.is_some(),
+            };
+            if stream_stalled {
+                let reason = camera.capture_stop_reason.as_ref().map(|r| format!(". Capture has stopped: {}", r)).unwrap_or_default();
+                alerts.push((ActiveAlert { alert: SystemAlert::StreamStalled, camera_id: Some(camera_id.clone()) }, format!("No frames from video source '{}' for {} ms{}", camera_id, camera.last_frame_age_ms.unwrap_or(0), reason)));
+            } else {
+                any_stream_alive = true;
+            }
+            if self.frozen_frames_limit > 0 && camera.identical_frames >= self.frozen_frames_limit {
+                alerts.push((ActiveAlert { alert: SystemAlert::FrameFrozen, camera_id: Some(camera_id.clone()) }, format!("Video source '{}' returns identical frames ({} in a row)", camera_id, camera.identical_frames)));
+            }
+        }
+        // Detection is considered stalled only while frames are still coming
+        if any_stream_alive {
+            if let Some(processed_age_ms) = state.last_processed_age_ms() {
+                if processed_age_ms > self.detection_stall_ms {
+                    alerts.push((ActiveAlert { alert: SystemAlert::DetectionStalled, camera_id: None }, format!("No frames have been processed for {} ms", processed_age_ms)));
+                }
+            }
+        }
+        alerts
+    }
+    /// Spawns thread which checks global health state every second and calls `on_change` when alert is raised or cleared.
+    /// Thread exits when `stop` is set
+    pub fn spawn<F>(self, stop: Arc<AtomicBool>, mut on_change: F)
+    where
+        F: FnMut(SystemEventType, &ActiveAlert, &str) + Send + 'static
+    {
+        thread::spawn(move || {
+            let mut active: Vec<ActiveAlert> = vec![];
+            loop {
+                thread::sleep(CHECK_INTERVAL);
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let current = self.check(health());
+                for (alert, message) in current.iter() {
+                    if !active.contains(alert) {
+                        eprintln!("[WATCHDOG] Alert raised: {}", message);
+                        on_change(SystemEventType::AlertRaised, alert, message);
+                    }
+                }
+                for alert in active.iter() {
+                    if !current.iter().any(|(a, _)| a == alert) {
+                        println!("[WATCHDOG] Alert cleared: {}{}", alert.alert.as_str(), alert.camera_id.as_ref().map(|id| format!(" ({})", id)).unwrap_or_default());
+                        on_change(SystemEventType::AlertCleared, alert, "Condition is not observed anymore");
+                    }
+                }
+                active = current.into_iter().map(|(alert, _)| alert).collect();
+                health().set_alerts(active.clone());
+            }
+        });
+    }
+}
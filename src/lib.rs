@@ -2,8 +2,15 @@ pub mod app;
 pub mod utils;
 pub mod video_capture;
 pub mod detection;
+pub mod detector;
 pub mod tracker;
 pub mod events;
 pub mod zones;
 pub mod draw;
 pub mod publisher;
+pub mod outbox;
+pub mod mjpeg;
+pub mod rest_api;
+pub mod metrics;
+pub mod health;
+pub mod background;
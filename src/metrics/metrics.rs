@@ -0,0 +1,120 @@
+use prometheus::{
+    Registry, Encoder, TextEncoder,
+    IntCounterVec, IntGauge, IntGaugeVec, GaugeVec, Histogram, HistogramOpts, Opts,
+};
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+// Buckets (in seconds) for processing stages: from 1ms to ~4s
+const LATENCY_BUCKETS: [f64; 12] = [0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0, 4.0];
+
+/// Application-wide Prometheus metrics
+pub struct Metrics {
+    registry: Registry,
+    /// Labels: camera_id
+    pub frames_read: IntCounterVec,
+    /// Labels: camera_id
+    pub empty_frames: IntCounterVec,
+    /// Labels: camera_id
+    pub capture_fps: GaugeVec,
+    /// Labels: camera_id
+    pub processing_fps: GaugeVec,
+    pub nn_forward_seconds: Histogram,
+    pub bg_subtraction_seconds: Histogram,
+    /// Labels: camera_id
+    pub active_tracks: IntGaugeVec,
+    /// Labels: camera_id, zone_id, class, event_type
+    pub events: IntCounterVec,
+    /// Labels: publisher, result ("success" or "failure")
+    pub publish: IntCounterVec,
+    /// Time the capture thread is blocked until detection thread takes the frame
+    pub frames_channel_wait_seconds: Histogram,
+    /// Time the detection thread is blocked until publishing thread takes the event
+    pub events_channel_wait_seconds: Histogram,
+    pub outbox_pending: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("road_anomaly".to_string()), None)?;
+        let metrics = Metrics {
+            frames_read: IntCounterVec::new(Opts::new("frames_read_total", "Number of frames read from the video source"), &["camera_id"])?,
+            empty_frames: IntCounterVec::new(Opts::new("empty_frames_total", "Number of empty frames returned by the video source"), &["camera_id"])?,
+            capture_fps: GaugeVec::new(Opts::new("capture_fps", "Frames per second read from the video source"), &["camera_id"])?,
+            processing_fps: GaugeVec::new(Opts::new("processing_fps", "Frames per second processed by detection loop"), &["camera_id"])?,
+            nn_forward_seconds: latency_histogram("nn_forward_seconds", "Latency of neural network forward pass")?,
+            bg_subtraction_seconds: latency_histogram("bg_subtraction_seconds", "Latency of background subtraction")?,
+            active_tracks: IntGaugeVec::new(Opts::new("active_tracks", "Number of objects tracked at the moment"), &["camera_id"])?,
+            events: IntCounterVec::new(Opts::new("events_total", "Number of emitted events"), &["camera_id", "zone_id", "class", "event_type"])?,
+            publish: IntCounterVec::new(Opts::new("publish_total", "Number of publishing attempts"), &["publisher", "result"])?,
+            frames_channel_wait_seconds: latency_histogram("frames_channel_wait_seconds", "Time spent waiting for detection loop to accept a frame")?,
+            events_channel_wait_seconds: latency_histogram("events_channel_wait_seconds", "Time spent waiting for publishing thread to accept an event")?,
+            outbox_pending: IntGauge::new("outbox_pending", "Number of events in the outbox which have not been delivered yet")?,
+            registry,
+        };
+        metrics.registry.register(Box::new(metrics.frames_read.clone()))?;
+        metrics.registry.register(Box::new(metrics.empty_frames.clone()))?;
+        metrics.registry.register(Box::new(metrics.capture_fps.clone()))?;
+        metrics.registry.register(Box::new(metrics.processing_fps.clone()))?;
+        metrics.registry.register(Box::new(metrics.nn_forward_seconds.clone()))?;
+        metrics.registry.register(Box::new(metrics.bg_subtraction_seconds.clone()))?;
+        metrics.registry.register(Box::new(metrics.active_tracks.clone()))?;
+        metrics.registry.register(Box::new(metrics.events.clone()))?;
+        metrics.registry.register(Box::new(metrics.publish.clone()))?;
+        metrics.registry.register(Box::new(metrics.frames_channel_wait_seconds.clone()))?;
+        metrics.registry.register(Box::new(metrics.events_channel_wait_seconds.clone()))?;
+        metrics.registry.register(Box::new(metrics.outbox_pending.clone()))?;
+        Ok(metrics)
+    }
+    /// Renders every metric in Prometheus text format
+    pub fn encode(&self) -> Result<(String, String), prometheus::Error> {
+        let encoder = TextEncoder::new();
+        let body = encoder.encode_to_string(&self.registry.gather())?;
+        Ok((encoder.format_type().to_string(), body))
+    }
+}
+
+fn latency_histogram(name: &str, help: &str) -> Result<Histogram, prometheus::Error> {
+    Histogram::with_opts(HistogramOpts::new(name, help).buckets(LATENCY_BUCKETS.to_vec()))
+}
+
+/// Global metrics. They are created on the first call
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| Metrics::new().expect("Metrics definitions should be valid"))
+}
+
+/// Counts frames and reports rate once per second
+pub struct FpsMeter {
+    window_start: Instant,
+    frames: u32,
+}
+
+impl Default for FpsMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FpsMeter {
+    pub fn new() -> Self {
+        FpsMeter {
+            window_start: Instant::now(),
+            frames: 0,
+        }
+    }
+    /// Registers one frame. Returns frames per second when one second window is over
+    pub fn tick(&mut self) -> Option<f64> {
+        self.frames += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed < Duration::from_secs(1) {
+            return None;
+        }
+        let fps = self.frames as f64 / elapsed.as_secs_f64();
+        self.window_start = Instant::now();
+        self.frames = 0;
+        Some(fps)
+    }
+}
@@ -0,0 +1,133 @@
+use crate::mjpeg::MjpegError;
+
+use opencv::{
+    core::Mat,
+    core::Size,
+    core::Vector,
+    imgcodecs::imencode,
+    imgcodecs::IMWRITE_JPEG_QUALITY,
+    imgproc::resize,
+};
+use tiny_http::{Server, Request, Response};
+
+use std::io::Write;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BOUNDARY: &str = "frame";
+
+/// Last encoded JPEG frame shared between the detection loop and HTTP clients
+struct SharedFrame {
+    frame: Mutex<(u64, Arc<Vec<u8>>)>,
+    updated: Condvar,
+    clients: AtomicUsize,
+}
+
+impl SharedFrame {
+    /// Blocks until frame with sequence number different from the given one is available
+    fn wait_next(&self, last_seq: u64) -> (u64, Arc<Vec<u8>>) {
+        let guard = self.frame.lock().unwrap_or_else(|e| e.into_inner());
+        let guard = self.updated.wait_while(guard, |(seq, _)| *seq == last_seq).unwrap_or_else(|e| e.into_inner());
+        (guard.0, guard.1.clone())
+    }
+}
+
+/// Serves annotated frames as "multipart/x-mixed-replace" stream via HTTP
+pub struct MjpegStreamer {
+    shared: Arc<SharedFrame>,
+    size: Option<Size>,
+    quality: i32,
+    min_interval: Duration,
+    last_push: Option<Instant>,
+    resized_frame: Mat,
+}
+
+impl MjpegStreamer {
+    /// Starts HTTP server in background. Any path except "/favicon.ico" returns the stream
+    pub fn start(host: &str, port: u16, size: Option<(i32, i32)>, quality: i32, max_fps: f32) -> Result<Self, MjpegError> {
+        let address = format!("{}:{}", host, port);
+        let server = Server::http(&address).map_err(|e| MjpegError::ServerError(format!("{}: {}", address, e)))?;
+        let shared = Arc::new(SharedFrame {
+            frame: Mutex::new((0, Arc::new(vec![]))),
+            updated: Condvar::new(),
+            clients: AtomicUsize::new(0),
+        });
+        let shared_server = shared.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let shared_client = shared_server.clone();
+                thread::spawn(move || {
+                    handle_client(request, shared_client);
+                });
+            }
+        });
+        println!("MJPEG streaming is available at http://{}/", address);
+        Ok(MjpegStreamer {
+            shared,
+            size: size.map(|(w, h)| Size::new(w, h)),
+            quality: quality.clamp(1, 100),
+            min_interval: if max_fps > 0.0 { Duration::from_secs_f32(1.0 / max_fps) } else { Duration::ZERO },
+            last_push: None,
+            resized_frame: Mat::default(),
+        })
+    }
+    /// Encodes frame and wakes up clients. Does nothing if nobody is watching or FPS limit is reached
+    pub fn push(&mut self, frame: &Mat) -> Result<(), MjpegError> {
+        if self.shared.clients.load(Ordering::Relaxed) == 0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        if let Some(last_push) = self.last_push {
+            if now.duration_since(last_push) < self.min_interval {
+                return Ok(());
+            }
+        }
+        self.last_push = Some(now);
+        let mut buf = Vector::new();
+        let params = Vector::from_slice(&[IMWRITE_JPEG_QUALITY, self.quality]);
+        match self.size {
+            Some(size) => {
+                resize(frame, &mut self.resized_frame, size, 0.0, 0.0, 1)?;
+                imencode(".jpg", &self.resized_frame, &mut buf, &params)?;
+            },
+            None => {
+                imencode(".jpg", frame, &mut buf, &params)?;
+            }
+        }
+        let mut guard = self.shared.frame.lock().unwrap_or_else(|e| e.into_inner());
+        *guard = (guard.0 + 1, Arc::new(buf.to_vec()));
+        self.shared.updated.notify_all();
+        Ok(())
+    }
+}
+
+fn handle_client(request: Request, shared: Arc<SharedFrame>) {
+    if request.url() == "/favicon.ico" {
+        let _ = request.respond(Response::empty(404));
+        return;
+    }
+    // Headers are written manually: every frame should be flushed immediately (no chunked encoding)
+    let mut writer = request.into_writer();
+    let header = format!("HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary={}\r\nCache-Control: no-cache, no-store\r\nPragma: no-cache\r\nConnection: close\r\n\r\n", BOUNDARY);
+    if writer.write_all(header.as_bytes()).and_then(|_| writer.flush()).is_err() {
+        return;
+    }
+    shared.clients.fetch_add(1, Ordering::Relaxed);
+    let mut last_seq: u64 = 0;
+    loop {
+        let (seq, jpeg) = shared.wait_next(last_seq);
+        last_seq = seq;
+        let part_header = format!("--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n", BOUNDARY, jpeg.len());
+        let sent = writer.write_all(part_header.as_bytes())
+            .and_then(|_| writer.write_all(&jpeg))
+            .and_then(|_| writer.write_all(b"\r\n"))
+            .and_then(|_| writer.flush());
+        if sent.is_err() {
+            // Client has disconnected
+            break;
+        }
+    }
+    shared.clients.fetch_sub(1, Ordering::Relaxed);
+}
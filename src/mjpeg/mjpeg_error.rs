@@ -0,0 +1,22 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum MjpegError {
+    ServerError(String),
+    OpenCVError(opencv::Error),
+}
+
+impl fmt::Display for MjpegError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MjpegError::ServerError(e) => write!(f, "Can't start MJPEG server: {}", e),
+            MjpegError::OpenCVError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<opencv::Error> for MjpegError {
+    fn from(e: opencv::Error) -> Self {
+        MjpegError::OpenCVError(e)
+    }
+}
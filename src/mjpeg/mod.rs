@@ -0,0 +1,4 @@
+mod mjpeg_error;
+mod mjpeg;
+
+pub use self::{mjpeg_error::*, mjpeg::*};
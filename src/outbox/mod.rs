@@ -0,0 +1,4 @@
+mod outbox_error;
+mod outbox;
+
+pub use self::{outbox_error::*, outbox::*};
@@ -0,0 +1,584 @@
+use crate::outbox::OutboxError;
+use crate::events::EventInfo;
+use crate::publisher::PublisherEntry;
+
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+// Outbox file is rewritten when it contains this many records more than needed to describe current backlog
+const COMPACT_MIN_RECORDS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    DropOldest,
+    DropNewest,
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "drop_oldest" => Ok(OverflowPolicy::DropOldest),
+            "drop_newest" => Ok(OverflowPolicy::DropNewest),
+            _ => Err(format!("Bad overflow policy: '{}'. Available: 'drop_oldest', 'drop_newest'", s)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RecordOut<'a> {
+    op: &'static str,
+    seq: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publisher: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publishers: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event: Option<&'a EventInfo>,
+}
+
+// Event payload is ignored while reading headers, so replay does not decode images
+#[derive(Deserialize)]
+struct RecordHeader {
+    op: String,
+    seq: u64,
+    publisher: Option<String>,
+    /// Publishers the event is addressed to. Absent in records written by older versions
+    publishers: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct AddRecord {
+    event: EventInfo,
+}
+
+struct Delivery {
+    publisher: String,
+    attempts: u32,
+    next_attempt: Instant,
+}
+
+struct PendingEvent {
+    seq: u64,
+    offset: u64,
+    length: u64,
+    /// Every publisher the event is addressed to
+    publishers: Vec<String>,
+    deliveries: Vec<Delivery>,
+}
+
+/// Append-only JSON lines journal of events. Each event is stored before publishing
+/// and is kept until every publisher has acknowledged it.
+///
+/// Records are:
+/// - `{"op":"add","seq":N,"publishers":["redis_0"],"event":{...}}` - new event and publishers it is addressed to;
+/// - `{"op":"ack","seq":N,"publisher":"redis_0"}` - event has been delivered by the publisher;
+/// - `{"op":"drop","seq":N}` - event has been dropped due the backlog overflow.
+///
+/// Deliveries are tracked by publisher names, so names should be stable between restarts. Deliveries via publishers
+/// which are not available on open (e.g. removed from configuration or failed to start) are discarded, so they neither block the backlog nor count toward its limit.
+pub struct Outbox {
+    path: PathBuf,
+    writer: File,
+    file_length: u64,
+    records_count: usize,
+    publishers: Vec<String>,
+    pending: VecDeque<PendingEvent>,
+    next_seq: u64,
+    max_events: usize,
+    overflow_policy: OverflowPolicy,
+    retry_initial: Duration,
+    retry_max: Duration,
+}
+
+impl Outbox {
+    /// Opens (or creates) outbox file and restores backlog of undelivered events.
+    /// Given publishers (ones which are actually available) are used for new events and for stored events which do not name their publishers
+    pub fn open(path: &str, publishers: Vec<String>, max_events: usize, overflow_policy: OverflowPolicy, retry_initial: Duration, retry_max: Duration) -> Result<Self, OutboxError> {
+        let path = PathBuf::from(path);
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let mut stored: BTreeMap<u64, (u64, u64, Vec<String>, HashSet<String>)> = BTreeMap::new();
+        let mut next_seq: u64 = 0;
+        let mut valid_length: u64 = 0;
+        let mut records_count: usize = 0;
+        if path.exists() {
+            let mut reader = BufReader::new(File::open(&path)?);
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let read_bytes = reader.read_line(&mut line)? as u64;
+                if read_bytes == 0 {
+                    break;
+                }
+                // Partially written record (e.g. power loss). Everything after it is discarded
+                if !line.ends_with('\n') {
+                    eprintln!("[WARNING]: Outbox '{}' contains incomplete record at offset {}. Truncating", path.display(), valid_length);
+                    break;
+                }
+                let header: RecordHeader = match serde_json::from_str(&line) {
+                    Ok(header) => header,
+                    Err(err) => {
+                        eprintln!("[WARNING]: Outbox '{}' contains bad record at offset {}: {}. Truncating", path.display(), valid_length, err);
+                        break;
+                    }
+                };
+                match header.op.as_str() {
+                    "add" => {
+                        let targets = header.publishers.unwrap_or_else(|| publishers.clone());
+                        stored.insert(header.seq, (valid_length, read_bytes, targets, HashSet::new()));
+                    },
+                    "ack" => {
+                        if let (Some(entry), Some(publisher)) = (stored.get_mut(&header.seq), header.publisher) {
+                            entry.3.insert(publisher);
+                        }
+                    },
+                    "drop" => {
+                        stored.remove(&header.seq);
+                    },
+                    _ => {}
+                }
+                next_seq = next_seq.max(header.seq + 1);
+                valid_length += read_bytes;
+                records_count += 1;
+            }
+        }
+        let writer = OpenOptions::new().create(true).append(true).open(&path)?;
+        writer.set_len(valid_length)?;
+        let now = Instant::now();
+        let mut discarded: Vec<String> = vec![];
+        let pending: VecDeque<PendingEvent> = stored.into_iter().filter_map(|(seq, (offset, length, targets, acked))| {
+            let (targets, unknown): (Vec<String>, Vec<String>) = targets.into_iter().partition(|name| publishers.contains(name));
+            discarded.extend(unknown.into_iter().filter(|name| !acked.contains(name)));
+            let deliveries: Vec<Delivery> = targets.iter().filter(|name| !acked.contains(*name)).map(|name| Delivery {
+                publisher: name.clone(),
+                attempts: 0,
+                next_attempt: now,
+            }).collect();
+            if deliveries.is_empty() {
+                return None;
+            }
+            Some(PendingEvent { seq, offset, length, publishers: targets, deliveries })
+        }).collect();
+        println!("Outbox '{}' has been opened. Undelivered events restored: {}", path.display(), pending.len());
+        if !discarded.is_empty() {
+            let deliveries_number = discarded.len();
+            discarded.sort_unstable();
+            discarded.dedup();
+            eprintln!("[WARNING]: Outbox '{}' has {} undelivered events for publishers which are not available: {:?}. They are discarded", path.display(), deliveries_number, discarded);
+        }
+        let mut outbox = Outbox {
+            path,
+            writer,
+            file_length: valid_length,
+            records_count,
+            publishers,
+            pending,
+            next_seq,
+            max_events,
+            overflow_policy,
+            retry_initial,
+            retry_max,
+        };
+        outbox.cleanup()?;
+        Ok(outbox)
+    }
+    /// Number of events which have not been delivered by every publisher yet
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+    /// Makes sure every written record (including acknowledgements) has reached the disk
+    pub fn flush(&mut self) -> Result<(), OutboxError> {
+        self.writer.sync_all()?;
+        Ok(())
+    }
+    /// Closest time when some of deliveries should be retried
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pending.iter().flat_map(|p| p.deliveries.iter().map(|d| d.next_attempt)).min()
+    }
+    /// Stores event durably. Returns its sequence number or None if event has been rejected due the overflow policy
+    pub fn push(&mut self, event: &EventInfo) -> Result<Option<u64>, OutboxError> {
+        if self.max_events > 0 && self.pending.len() >= self.max_events {
+            match self.overflow_policy {
+                OverflowPolicy::DropNewest => {
+                    eprintln!("[WARNING]: Outbox is full ({} events). Dropping new event '{}'", self.pending.len(), event.get_id());
+                    return Ok(None);
+                },
+                OverflowPolicy::DropOldest => {
+                    if let Some(oldest) = self.pending.pop_front() {
+                        eprintln!("[WARNING]: Outbox is full ({} events). Dropping oldest event with seq {}", self.max_events, oldest.seq);
+                        self.write_record(&RecordOut { op: "drop", seq: oldest.seq, publisher: None, publishers: None, event: None }, false)?;
+                    }
+                }
+            }
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let publishers = self.publishers.clone();
+        let (offset, length) = self.write_record(&RecordOut { op: "add", seq, publisher: None, publishers: Some(&publishers), event: Some(event) }, true)?;
+        let now = Instant::now();
+        self.pending.push_back(PendingEvent {
+            seq,
+            offset,
+            length,
+            publishers,
+            deliveries: self.publishers.iter().map(|name| Delivery {
+                publisher: name.clone(),
+                attempts: 0,
+                next_attempt: now,
+            }).collect(),
+        });
+        Ok(Some(seq))
+    }
+    /// Tries to deliver freshly pushed event which is still in memory (avoids reading it back from disk)
+    pub fn deliver_event(&mut self, seq: u64, event: &EventInfo, publishers: &[PublisherEntry]) -> Result<(), OutboxError> {
+        if let Some(idx) = self.pending.iter().position(|p| p.seq == seq) {
+            self.attempt(idx, event, publishers, Instant::now())?;
+        }
+        self.cleanup()
+    }
+    /// Retries every delivery which backoff has been expired
+    pub fn deliver_due(&mut self, publishers: &[PublisherEntry]) -> Result<(), OutboxError> {
+        let now = Instant::now();
+        for idx in 0..self.pending.len() {
+            if !self.pending[idx].deliveries.iter().any(|d| d.next_attempt <= now) {
+                continue;
+            }
+            let event = match self.read_event(self.pending[idx].offset, self.pending[idx].length) {
+                Ok(event) => event,
+                Err(OutboxError::SerdeError(err)) => {
+                    let seq = self.pending[idx].seq;
+                    eprintln!("Can't decode event with seq {} from outbox: {}. Dropping it", seq, err);
+                    self.pending[idx].deliveries.clear();
+                    self.write_record(&RecordOut { op: "drop", seq, publisher: None, publishers: None, event: None }, false)?;
+                    continue;
+                },
+                Err(err) => return Err(err),
+            };
+            self.attempt(idx, &event, publishers, now)?;
+        }
+        self.cleanup()
+    }
+    fn attempt(&mut self, idx: usize, event: &EventInfo, publishers: &[PublisherEntry], now: Instant) -> Result<(), OutboxError> {
+        let retry_initial = self.retry_initial;
+        let retry_max = self.retry_max;
+        let pending = &mut self.pending[idx];
+        let mut acked: Vec<String> = vec![];
+        let mut lost: Vec<String> = vec![];
+        for delivery in pending.deliveries.iter_mut() {
+            if delivery.next_attempt > now {
+                continue;
+            }
+            let entry = match publishers.iter().find(|p| p.name == delivery.publisher) {
+                Some(entry) => entry,
+                None => {
+                    // Outbox is opened with available publishers only, so it is not expected. Delivery would never happen otherwise
+                    eprintln!("Publisher '{}' is not available. Discarding delivery of event with seq {}", delivery.publisher, pending.seq);
+                    lost.push(delivery.publisher.clone());
+                    continue;
+                }
+            };
+            match entry.publish(event) {
+                Ok(_) => acked.push(delivery.publisher.clone()),
+                Err(err) => {
+                    delivery.attempts += 1;
+                    let delay = backoff(retry_initial, retry_max, delivery.attempts);
+                    delivery.next_attempt = now + delay;
+                    eprintln!("Error during publishing message via '{}' (attempt {}). Next retry in {:?}: {:#?}", delivery.publisher, delivery.attempts, delay, err);
+                }
+            };
+        }
+        pending.deliveries.retain(|d| !acked.contains(&d.publisher) && !lost.contains(&d.publisher));
+        pending.publishers.retain(|name| !lost.contains(name));
+        let seq = pending.seq;
+        for publisher in acked.iter() {
+            self.write_record(&RecordOut { op: "ack", seq, publisher: Some(publisher), publishers: None, event: None }, false)?;
+        }
+        Ok(())
+    }
+    fn read_event(&self, offset: u64, length: u64) -> Result<EventInfo, OutboxError> {
+        let mut reader = File::open(&self.path)?;
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut buf = vec![0; length as usize];
+        reader.read_exact(&mut buf)?;
+        let record: AddRecord = serde_json::from_slice(&buf)?;
+        Ok(record.event)
+    }
+    /// Appends record to the file. Returns its offset and length
+    fn write_record(&mut self, record: &RecordOut, sync: bool) -> Result<(u64, u64), OutboxError> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        if sync {
+            self.writer.sync_data()?;
+        }
+        let offset = self.file_length;
+        self.file_length += line.len() as u64;
+        self.records_count += 1;
+        Ok((offset, line.len() as u64))
+    }
+    /// Forgets fully delivered events and shrinks the file when it is needed
+    fn cleanup(&mut self) -> Result<(), OutboxError> {
+        self.pending.retain(|p| !p.deliveries.is_empty());
+        if self.pending.is_empty() {
+            if self.file_length > 0 {
+                self.writer.set_len(0)?;
+                self.file_length = 0;
+                self.records_count = 0;
+            }
+            return Ok(());
+        }
+        let needed_records: usize = self.pending.iter().map(|p| p.publishers.len() + 1).sum();
+        if self.records_count > needed_records + COMPACT_MIN_RECORDS {
+            self.compact()?;
+        }
+        Ok(())
+    }
+    fn compact(&mut self) -> Result<(), OutboxError> {
+        let tmp_path = self.path.with_extension("compact");
+        let mut reader = File::open(&self.path)?;
+        let mut tmp_writer = File::create(&tmp_path)?;
+        let mut file_length: u64 = 0;
+        let mut records_count: usize = 0;
+        let mut new_offsets: Vec<u64> = Vec::with_capacity(self.pending.len());
+        for pending in self.pending.iter() {
+            reader.seek(SeekFrom::Start(pending.offset))?;
+            let mut buf = vec![0; pending.length as usize];
+            reader.read_exact(&mut buf)?;
+            tmp_writer.write_all(&buf)?;
+            new_offsets.push(file_length);
+            file_length += pending.length;
+            records_count += 1;
+            for publisher in pending.publishers.iter().filter(|name| !pending.deliveries.iter().any(|d| &d.publisher == *name)) {
+                let mut line = serde_json::to_string(&RecordOut { op: "ack", seq: pending.seq, publisher: Some(publisher), publishers: None, event: None })?;
+                line.push('\n');
+                tmp_writer.write_all(line.as_bytes())?;
+                file_length += line.len() as u64;
+                records_count += 1;
+            }
+        }
+        tmp_writer.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        self.writer = OpenOptions::new().append(true).open(&self.path)?;
+        for (pending, offset) in self.pending.iter_mut().zip(new_offsets) {
+            pending.offset = offset;
+        }
+        self.file_length = file_length;
+        self.records_count = records_count;
+        Ok(())
+    }
+}
+
+fn backoff(initial: Duration, max: Duration, attempts: u32) -> Duration {
+    let factor = 2_u32.saturating_pow(attempts.saturating_sub(1));
+    initial.saturating_mul(factor).min(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{EventBBox, EventPOI};
+    use crate::publisher::{PublisherTrait, PublisherError, PublisherInternalError};
+
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    /// Outbox file in temporary directory which is removed after the test
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new() -> Self {
+            TempPath(std::env::temp_dir().join(format!("outbox-test-{}.jsonl", Uuid::new_v4())))
+        }
+        fn as_str(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+            let _ = fs::remove_file(self.0.with_extension("compact"));
+        }
+    }
+
+    struct FakePublisher {
+        fail: bool,
+        delivered: Arc<Mutex<Vec<Uuid>>>,
+    }
+
+    impl PublisherTrait for FakePublisher {
+        fn publish(&self, event: &EventInfo) -> Result<(), PublisherError> {
+            if self.fail {
+                return Err(PublisherError::from(PublisherInternalError("unavailable".to_string())));
+            }
+            self.delivered.lock().unwrap().push(event.get_id());
+            Ok(())
+        }
+    }
+
+    fn publisher(name: &str, fail: bool) -> (PublisherEntry, Arc<Mutex<Vec<Uuid>>>) {
+        let delivered = Arc::new(Mutex::new(vec![]));
+        let entry = PublisherEntry { name: name.to_string(), publisher: Box::new(FakePublisher { fail, delivered: delivered.clone() }) };
+        (entry, delivered)
+    }
+
+    fn event() -> EventInfo {
+        EventInfo::new(1_700_000_000, None, Uuid::new_v4().to_string(), 1_700_000_000, 5, EventBBox{x: 1, y: 2, width: 3, height: 4}, EventPOI{x: 2, y: 4}, "accident".to_string(), 0.9, "zone_1".to_string(), None)
+    }
+
+    fn open(path: &TempPath, publishers: &[&str], max_events: usize, overflow_policy: OverflowPolicy) -> Outbox {
+        let names = publishers.iter().map(|name| name.to_string()).collect();
+        Outbox::open(path.as_str(), names, max_events, overflow_policy, Duration::ZERO, Duration::from_secs(60)).unwrap()
+    }
+
+    fn pending_seqs(outbox: &Outbox) -> Vec<u64> {
+        outbox.pending.iter().map(|p| p.seq).collect()
+    }
+
+    fn pending_publishers(outbox: &Outbox, seq: u64) -> Vec<String> {
+        outbox.pending.iter().find(|p| p.seq == seq).unwrap().deliveries.iter().map(|d| d.publisher.clone()).collect()
+    }
+
+    #[test]
+    fn reopen_restores_unacked_deliveries_only() {
+        let path = TempPath::new();
+        let (a_ok, _) = publisher("a", false);
+        let (b_ok, _) = publisher("b", false);
+        let (b_down, _) = publisher("b", true);
+        let events = [event(), event(), event()];
+        {
+            let mut outbox = open(&path, &["a", "b"], 0, OverflowPolicy::DropNewest);
+            let seqs: Vec<u64> = events.iter().map(|e| outbox.push(e).unwrap().unwrap()).collect();
+            outbox.deliver_event(seqs[0], &events[0], &[a_ok, b_ok]).unwrap();
+            let (a_ok, _) = publisher("a", false);
+            outbox.deliver_event(seqs[1], &events[1], &[a_ok, b_down]).unwrap();
+            assert_eq!(pending_seqs(&outbox), vec![1, 2]);
+        }
+        let outbox = open(&path, &["a", "b"], 0, OverflowPolicy::DropNewest);
+        assert_eq!(pending_seqs(&outbox), vec![1, 2]);
+        assert_eq!(pending_publishers(&outbox, 1), vec!["b"]);
+        assert_eq!(pending_publishers(&outbox, 2), vec!["a", "b"]);
+        assert_eq!(outbox.next_seq, 3);
+    }
+
+    #[test]
+    fn reopen_discards_deliveries_to_unavailable_publishers() {
+        let path = TempPath::new();
+        {
+            let mut outbox = open(&path, &["a", "b"], 1, OverflowPolicy::DropNewest);
+            outbox.push(&event()).unwrap().unwrap();
+        }
+        let mut outbox = open(&path, &["a"], 1, OverflowPolicy::DropNewest);
+        assert_eq!(pending_publishers(&outbox, 0), vec!["a"]);
+        let (a_ok, delivered) = publisher("a", false);
+        outbox.deliver_due(&[a_ok]).unwrap();
+        assert_eq!(delivered.lock().unwrap().len(), 1);
+        // Backlog is not blocked by the removed publisher
+        assert!(outbox.is_empty());
+        assert!(outbox.push(&event()).unwrap().is_some());
+    }
+
+    #[test]
+    fn truncated_last_record_is_discarded() {
+        let path = TempPath::new();
+        let stored = event();
+        let valid_length = {
+            let mut outbox = open(&path, &["a"], 0, OverflowPolicy::DropNewest);
+            outbox.push(&stored).unwrap().unwrap();
+            outbox.file_length
+        };
+        let mut file = OpenOptions::new().append(true).open(&path.0).unwrap();
+        file.write_all(b"{\"op\":\"ack\",\"seq\":0,\"publ").unwrap();
+        drop(file);
+        let mut outbox = open(&path, &["a"], 0, OverflowPolicy::DropNewest);
+        assert_eq!(pending_seqs(&outbox), vec![0]);
+        assert_eq!(fs::metadata(&path.0).unwrap().len(), valid_length);
+        // Appending after truncation keeps the journal readable
+        outbox.push(&event()).unwrap().unwrap();
+        drop(outbox);
+        let outbox = open(&path, &["a"], 0, OverflowPolicy::DropNewest);
+        assert_eq!(pending_seqs(&outbox), vec![0, 1]);
+        assert_eq!(outbox.read_event(outbox.pending[0].offset, outbox.pending[0].length).unwrap().get_id(), stored.get_id());
+    }
+
+    #[test]
+    fn drop_newest_rejects_events_over_limit() {
+        let path = TempPath::new();
+        let mut outbox = open(&path, &["a"], 2, OverflowPolicy::DropNewest);
+        assert_eq!(outbox.push(&event()).unwrap(), Some(0));
+        assert_eq!(outbox.push(&event()).unwrap(), Some(1));
+        assert_eq!(outbox.push(&event()).unwrap(), None);
+        assert_eq!(pending_seqs(&outbox), vec![0, 1]);
+        drop(outbox);
+        let outbox = open(&path, &["a"], 2, OverflowPolicy::DropNewest);
+        assert_eq!(pending_seqs(&outbox), vec![0, 1]);
+    }
+
+    #[test]
+    fn drop_oldest_evicts_first_event() {
+        let path = TempPath::new();
+        let mut outbox = open(&path, &["a"], 2, OverflowPolicy::DropOldest);
+        outbox.push(&event()).unwrap().unwrap();
+        outbox.push(&event()).unwrap().unwrap();
+        assert_eq!(outbox.push(&event()).unwrap(), Some(2));
+        assert_eq!(pending_seqs(&outbox), vec![1, 2]);
+        drop(outbox);
+        // Eviction is journaled, so it survives restart
+        let outbox = open(&path, &["a"], 2, OverflowPolicy::DropOldest);
+        assert_eq!(pending_seqs(&outbox), vec![1, 2]);
+    }
+
+    #[test]
+    fn compaction_preserves_pending_events() {
+        let path = TempPath::new();
+        let mut outbox = open(&path, &["a", "b"], 0, OverflowPolicy::DropNewest);
+        let stuck = event();
+        let stuck_seq = outbox.push(&stuck).unwrap().unwrap();
+        let (a_ok, _) = publisher("a", false);
+        let (b_down, _) = publisher("b", true);
+        outbox.deliver_event(stuck_seq, &stuck, &[a_ok, b_down]).unwrap();
+        let size_before = outbox.file_length;
+        let mut compacted = false;
+        for _ in 0..COMPACT_MIN_RECORDS {
+            let records_before = outbox.records_count;
+            let delivered = event();
+            let seq = outbox.push(&delivered).unwrap().unwrap();
+            let (a_ok, _) = publisher("a", false);
+            let (b_ok, _) = publisher("b", false);
+            outbox.deliver_event(seq, &delivered, &[a_ok, b_ok]).unwrap();
+            if outbox.records_count < records_before {
+                compacted = true;
+                break;
+            }
+        }
+        assert!(compacted);
+        // Compaction leaves stuck event and its acknowledgement only
+        assert_eq!(outbox.records_count, 2);
+        assert_eq!(outbox.file_length, size_before);
+        assert_eq!(fs::metadata(&path.0).unwrap().len(), size_before);
+        assert_eq!(pending_seqs(&outbox), vec![stuck_seq]);
+        assert_eq!(pending_publishers(&outbox, stuck_seq), vec!["b"]);
+        drop(outbox);
+        let mut outbox = open(&path, &["a", "b"], 0, OverflowPolicy::DropNewest);
+        assert_eq!(pending_seqs(&outbox), vec![stuck_seq]);
+        assert_eq!(pending_publishers(&outbox, stuck_seq), vec!["b"]);
+        let (a_ok, delivered_a) = publisher("a", false);
+        let (b_ok, delivered_b) = publisher("b", false);
+        outbox.deliver_due(&[a_ok, b_ok]).unwrap();
+        assert!(delivered_a.lock().unwrap().is_empty());
+        assert_eq!(*delivered_b.lock().unwrap(), vec![stuck.get_id()]);
+        assert!(outbox.is_empty());
+    }
+}
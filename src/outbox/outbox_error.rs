@@ -0,0 +1,28 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum OutboxError {
+    IOError(std::io::Error),
+    SerdeError(serde_json::Error),
+}
+
+impl fmt::Display for OutboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutboxError::IOError(e) => write!(f, "Outbox IO error: {}", e),
+            OutboxError::SerdeError(e) => write!(f, "Outbox serialization error: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for OutboxError {
+    fn from(e: std::io::Error) -> Self {
+        OutboxError::IOError(e)
+    }
+}
+
+impl From<serde_json::Error> for OutboxError {
+    fn from(e: serde_json::Error) -> Self {
+        OutboxError::SerdeError(e)
+    }
+}
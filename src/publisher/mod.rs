@@ -1,4 +1,7 @@
 pub mod publisher;
 pub mod redis_publisher;
+pub mod webhook_publisher;
+pub mod mqtt_publisher;
+pub mod registry;
 
-pub use self::{publisher::*, redis_publisher::*};
+pub use self::{publisher::*, redis_publisher::*, webhook_publisher::*, mqtt_publisher::*, registry::*};
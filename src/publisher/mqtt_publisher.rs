@@ -0,0 +1,136 @@
+use crate::publisher::publisher::{PublisherTrait, PublisherError, PublisherInternalError};
+use crate::publisher::registry::PublisherSettingsTrait;
+use crate::events::events::EventInfo;
+use crate::events::system_events::SystemEvent;
+use crate::app::MqttPublisherSettings;
+
+use rumqttc::{Client, MqttOptions, QoS, Transport};
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_TOPIC_TEMPLATE: &str = "anomalies/{equipment_id}/{zone_id}";
+const REQUESTS_CAPACITY: usize = 64;
+
+pub struct MqttConnection {
+    pub topic_template: String,
+    pub qos: QoS,
+    pub retain: bool,
+    client: Client,
+}
+
+impl MqttConnection {
+    /// Creates client and spawns thread which drives network event loop (including reconnects)
+    pub fn new(options: MqttOptions, topic_template: String, qos: QoS, retain: bool) -> Box<dyn PublisherTrait> {
+        let (client, mut connection) = Client::new(options, REQUESTS_CAPACITY);
+        thread::spawn(move || {
+            for notification in connection.iter() {
+                if let Err(err) = notification {
+                    eprintln!("MQTT connection error: {}. Reconnecting", err);
+                    thread::sleep(Duration::from_secs(1));
+                }
+            }
+        });
+        Box::new(MqttConnection {
+            topic_template: topic_template_handler(topic_template),
+            qos,
+            retain,
+            client,
+        })
+    }
+    /// Substitutes {equipment_id}, {camera_id}, {zone_id} and {object_classname} placeholders in topic template
+    pub fn topic_for(&self, event_info: &EventInfo) -> String {
+        self.topic_template
+            .replace("{equipment_id}", &event_info.get_equipment_id().unwrap_or_default())
+            .replace("{camera_id}", &event_info.get_camera_id())
+            .replace("{zone_id}", &event_info.get_zone_id())
+            .replace("{object_classname}", &event_info.get_classname())
+    }
+    /// System events use "system" as zone and alert name as class
+    pub fn topic_for_system(&self, event: &SystemEvent) -> String {
+        self.topic_template
+            .replace("{equipment_id}", &event.get_equipment_id().unwrap_or_default())
+            .replace("{camera_id}", &event.get_camera_id().unwrap_or_default())
+            .replace("{zone_id}", "system")
+            .replace("{object_classname}", event.get_alert().as_str())
+    }
+}
+
+impl PublisherTrait for MqttConnection {
+    /// `Ok` means that the message has been queued in the rumqttc client, not acknowledged by the broker.
+    /// Outbox acks delivery on it, so messages queued right before the crash or disconnect could be lost
+    fn publish(&self, event_info: &EventInfo) -> Result<(), PublisherError> {
+        let event_id = event_info.get_id();
+        let object_id = event_info.get_object_id();
+        let topic = self.topic_for(event_info);
+        println!("Trying to send event via MQTT: {}. Object ID: '{}'. Topic: '{}'. Time: {}", event_id, object_id, topic, chrono::Utc::now());
+        let event_json_str = serde_json::to_string(event_info)?;
+        self.client.publish(topic, self.qos, self.retain, event_json_str)?;
+        println!("Success for enqueueing MQTT event: '{}'. Object ID: '{}'. Time: {}", event_id, object_id, chrono::Utc::now());
+        Ok(())
+    }
+    fn publish_system(&self, event: &SystemEvent) -> Result<(), PublisherError> {
+        let topic = self.topic_for_system(event);
+        let event_json_str = serde_json::to_string(event)?;
+        self.client.publish(topic, self.qos, self.retain, event_json_str)?;
+        println!("Success for enqueueing MQTT system event: '{}'. Time: {}", event.get_id(), chrono::Utc::now());
+        Ok(())
+    }
+}
+
+impl PublisherSettingsTrait for MqttPublisherSettings {
+    fn kind(&self) -> &'static str {
+        "mqtt"
+    }
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    fn is_enabled(&self) -> bool {
+        self.enable
+    }
+    fn build(&self) -> Result<Box<dyn PublisherTrait>, PublisherError> {
+        let qos = rumqttc::qos(self.qos.unwrap_or(0))
+            .map_err(|e| PublisherInternalError(format!("Bad MQTT QoS: {:?}", e)))?;
+        let client_id = match &self.client_id {
+            Some(id) if !id.is_empty() => id.clone(),
+            _ => format!("road-anomaly-detection-{}", uuid::Uuid::new_v4()),
+        };
+        let mut options = MqttOptions::new(client_id, self.host.clone(), self.port);
+        options.set_keep_alive(Duration::from_secs(self.keep_alive_seconds.unwrap_or(30)));
+        if let Some(username) = self.username.as_ref().filter(|username| !username.is_empty()) {
+            options.set_credentials(username.clone(), self.password.clone().unwrap_or_default());
+        }
+        if let Some(tls) = &self.tls {
+            if tls.enable {
+                options.set_transport(prepare_tls_transport(tls)?);
+            }
+        }
+        Ok(MqttConnection::new(options, self.topic.clone(), qos, self.retain.unwrap_or(false)))
+    }
+}
+
+fn prepare_tls_transport(tls: &crate::app::MqttTlsSettings) -> Result<Transport, PublisherError> {
+    let ca = fs::read(&tls.ca_path)
+        .map_err(|e| PublisherInternalError(format!("Can't read CA file '{}': {}", tls.ca_path, e)))?;
+    let client_auth = match (&tls.client_cert_path, &tls.client_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = fs::read(cert_path)
+                .map_err(|e| PublisherInternalError(format!("Can't read client certificate '{}': {}", cert_path, e)))?;
+            let key = fs::read(key_path)
+                .map_err(|e| PublisherInternalError(format!("Can't read client key '{}': {}", key_path, e)))?;
+            Some((cert, key))
+        },
+        (None, None) => None,
+        _ => {
+            return Err(PublisherError::from(PublisherInternalError("Both 'client_cert_path' and 'client_key_path' should be provided for MQTT client authentication".to_string())));
+        }
+    };
+    Ok(Transport::tls(ca, client_auth, None))
+}
+
+fn topic_template_handler(s: String) -> String {
+    if s.chars().count() != 0 {
+        return s;
+    }
+    DEFAULT_TOPIC_TEMPLATE.to_string()
+}
@@ -1,14 +1,16 @@
-use crate::events::EventInfo;
+use crate::events::{EventInfo, SystemEvent};
 use redis::RedisError;
 
 #[derive(Debug)]
-pub struct PublisherInternalError(String);
+pub struct PublisherInternalError(pub String);
 
 #[derive(Debug)]
 pub enum PublisherError{
     Er(PublisherInternalError),
     RedisErr(RedisError),
-    SerdeErr(serde_json::Error)
+    SerdeErr(serde_json::Error),
+    HttpErr(Box<ureq::Error>),
+    MqttErr(rumqttc::ClientError),
 }
 
 impl From<PublisherInternalError> for PublisherError {
@@ -29,6 +31,18 @@ impl From<serde_json::Error> for PublisherError {
     }
 }
 
+impl From<ureq::Error> for PublisherError {
+    fn from(e: ureq::Error) -> Self {
+        PublisherError::HttpErr(Box::new(e))
+    }
+}
+
+impl From<rumqttc::ClientError> for PublisherError {
+    fn from(e: rumqttc::ClientError) -> Self {
+        PublisherError::MqttErr(e)
+    }
+}
+
 impl std::fmt::Display for PublisherInternalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "PublisherInternalError: {}", self.0)
@@ -37,4 +51,8 @@ impl std::fmt::Display for PublisherInternalError {
 
 pub trait PublisherTrait {
     fn publish(&self, event: &EventInfo) -> Result<(), PublisherError>;
+    /// Publishes event about the state of the application (e.g. watchdog alerts). Publishers which do not support it ignore such events
+    fn publish_system(&self, _event: &SystemEvent) -> Result<(), PublisherError> {
+        Ok(())
+    }
 }
@@ -1,45 +1,109 @@
-use crate::publisher::publisher::{PublisherTrait, PublisherError};
+use crate::publisher::publisher::{PublisherTrait, PublisherError, PublisherInternalError};
+use crate::publisher::registry::PublisherSettingsTrait;
 use crate::events::events::EventInfo;
+use crate::events::system_events::SystemEvent;
+use crate::app::RedisPublisherSettings;
 
-use redis::{Client, Commands};
-use std::sync::Arc;
+use redis::{Client, Commands, Connection, ConnectionLike};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedisMode {
+    /// Fire-and-forget PUBLISH into the channel
+    PubSub,
+    /// XADD into the stream (so consumers could use consumer groups and catch up after downtime)
+    Stream,
+}
+
+impl FromStr for RedisMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pubsub" => Ok(RedisMode::PubSub),
+            "stream" => Ok(RedisMode::Stream),
+            _ => Err(format!("Bad Redis mode: '{}'. Available: 'pubsub', 'stream'", s)),
+        }
+    }
+}
 
 pub struct RedisConnection {
     pub channel_name: String,
     pub client: Arc<Client>,
+    pub mode: RedisMode,
+    /// Max length of the stream. Trimming is approximate ("MAXLEN ~") since it is much more efficient
+    pub stream_maxlen: Option<usize>,
+    connection: Mutex<Option<Connection>>,
 }
 
 impl RedisConnection {
     pub fn new(host: String, port: i32, db_index: i32, channel_name: String) -> Result<Box<dyn PublisherTrait>, redis::RedisError> {
-        let client = Client::open(format!("redis://{}:{}/{}", host, port, db_index))?;
-        Ok(Box::new(RedisConnection {
-            channel_name: channel_name_handler(channel_name),
-            client: Arc::new(client),
-        }))
+        let client = Client::open(redis_url(&host, port, db_index, "", ""))?;
+        Ok(Box::new(RedisConnection::from_client(client, channel_name)))
     }
     pub fn new_with_password(host: String, port: i32, db_index: i32, channel_name: String, password: String) -> Result<Box<dyn PublisherTrait>, redis::RedisError> {
-        let client = Client::open(format!(
-            "redis://:{}@{}:{}/{}",
-            password, host, port, db_index
-        ))?;
-        Ok(Box::new(RedisConnection {
-            channel_name: channel_name_handler(channel_name), 
-            client: Arc::new(client),
-        }))
+        let client = Client::open(redis_url(&host, port, db_index, "", &password))?;
+        Ok(Box::new(RedisConnection::from_client(client, channel_name)))
     }
     pub fn new_with_username_password(host: String, port: i32, db_index: i32, channel_name: String, username: String, password: String) -> Result<Box<dyn PublisherTrait>, redis::RedisError> {
-        let client = Client::open(format!(
-            "redis://{}:{}@{}:{}/{}",
-            username, password, host, port, db_index
-        ))?;
-        Ok(Box::new(RedisConnection {
-            channel_name: channel_name_handler(channel_name), 
+        let client = Client::open(redis_url(&host, port, db_index, &username, &password))?;
+        Ok(Box::new(RedisConnection::from_client(client, channel_name)))
+    }
+    fn from_client(client: Client, channel_name: String) -> Self {
+        RedisConnection {
+            channel_name: channel_name_handler(channel_name),
             client: Arc::new(client),
-        }))
+            mode: RedisMode::PubSub,
+            stream_maxlen: None,
+            connection: Mutex::new(None),
+        }
     }
     pub fn set_channel(&mut self, _channel_name: String) {
         self.channel_name = channel_name_handler(_channel_name);
     }
+    pub fn set_mode(&mut self, mode: RedisMode, stream_maxlen: Option<usize>) {
+        self.mode = mode;
+        self.stream_maxlen = stream_maxlen;
+    }
+    /// Reuses connection while it is alive. Reconnects (once per message) if it has been broken
+    fn send_with_reconnect(&self, json_str: &str) -> Result<(), PublisherError> {
+        let mut guard = self.connection.lock().map_err(|_| PublisherInternalError("Redis connection mutex is poisoned".to_string()))?;
+        if let Some(conn) = guard.as_mut() {
+            match self.send(conn, json_str) {
+                Ok(_) => {
+                    return Ok(());
+                },
+                Err(err) => {
+                    if conn.is_open() && !err.is_io_error() && !err.is_connection_dropped() {
+                        return Err(PublisherError::from(err));
+                    }
+                    eprintln!("Redis connection has been lost: {}. Reconnecting", err);
+                    *guard = None;
+                }
+            }
+        }
+        let mut conn = self.client.get_connection()?;
+        self.send(&mut conn, json_str)?;
+        *guard = Some(conn);
+        Ok(())
+    }
+    fn send(&self, conn: &mut Connection, event_json_str: &str) -> Result<(), redis::RedisError> {
+        match self.mode {
+            RedisMode::PubSub => {
+                conn.publish::<_, _, ()>(self.channel_name.as_str(), event_json_str)?;
+            },
+            RedisMode::Stream => {
+                let mut cmd = redis::cmd("XADD");
+                cmd.arg(self.channel_name.as_str());
+                if let Some(maxlen) = self.stream_maxlen {
+                    cmd.arg("MAXLEN").arg("~").arg(maxlen);
+                }
+                cmd.arg("*").arg("event").arg(event_json_str);
+                cmd.query::<String>(conn)?;
+            }
+        };
+        Ok(())
+    }
 }
 
 impl PublisherTrait for RedisConnection {
@@ -47,12 +111,49 @@ impl PublisherTrait for RedisConnection {
         let event_id = event_info.get_id();
         let object_id = event_info.get_object_id();
         println!("Trying to send event: {}. Object ID: '{}'. Time: {}", event_id, object_id, chrono::Utc::now());
-        let mut redis_conn = self.client.get_connection()?;
         let event_json_str = serde_json::to_string(event_info)?;
-        redis_conn.publish(self.channel_name.to_owned(), event_json_str)?;
+        self.send_with_reconnect(&event_json_str)?;
         println!("Success for sending event: '{}'. Object ID: '{}'. Time: {}", event_id, object_id, chrono::Utc::now());
         Ok(())
     }
+    fn publish_system(&self, event: &SystemEvent) -> Result<(), PublisherError> {
+        let event_json_str = serde_json::to_string(event)?;
+        self.send_with_reconnect(&event_json_str)?;
+        println!("Success for sending system event: '{}'. Time: {}", event.get_id(), chrono::Utc::now());
+        Ok(())
+    }
+}
+
+impl PublisherSettingsTrait for RedisPublisherSettings {
+    fn kind(&self) -> &'static str {
+        "redis"
+    }
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    fn is_enabled(&self) -> bool {
+        self.enable
+    }
+    fn build(&self) -> Result<Box<dyn PublisherTrait>, PublisherError> {
+        let settings = self.clone();
+        let mode = match &settings.mode {
+            Some(m) => m.parse::<RedisMode>().map_err(PublisherInternalError)?,
+            None => RedisMode::PubSub
+        };
+        let url = redis_url(&settings.host, settings.port, settings.db_index, &settings.username, &settings.password);
+        let mut redis_conn = RedisConnection::from_client(Client::open(url)?, settings.channel_name);
+        redis_conn.set_mode(mode, settings.stream_maxlen);
+        Ok(Box::new(redis_conn))
+    }
+}
+
+/// Connection URL. Credentials are omitted if password is empty, username is omitted if it is empty
+fn redis_url(host: &str, port: i32, db_index: i32, username: &str, password: &str) -> String {
+    if password.is_empty() {
+        format!("redis://{}:{}/{}", host, port, db_index)
+    } else {
+        format!("redis://{}:{}@{}:{}/{}", username, password, host, port, db_index)
+    }
 }
 
 fn channel_name_handler(s: String) -> String {
@@ -0,0 +1,104 @@
+use crate::publisher::publisher::{PublisherTrait, PublisherError};
+use crate::events::{EventInfo, SystemEvent};
+use crate::metrics::metrics;
+use crate::health::health;
+
+use std::collections::HashMap;
+
+/// Configuration section which is able to construct a publisher of its own kind.
+/// In order to add new publisher kind just implement this trait for its settings
+/// and register those settings in `PublishersSettings::registry()`
+pub trait PublisherSettingsTrait {
+    /// Human-readable publisher kind (e.g. "redis", "webhook")
+    fn kind(&self) -> &'static str;
+    /// Name from the configuration. Outbox tracks deliveries by it, so it should not change between restarts
+    fn name(&self) -> Option<&str> {
+        None
+    }
+    fn is_enabled(&self) -> bool;
+    fn build(&self) -> Result<Box<dyn PublisherTrait>, PublisherError>;
+}
+
+/// Constructed publisher with unique (in scope of the registry) name: either configured one or "<kind>_<index of this kind>"
+pub struct PublisherEntry {
+    pub name: String,
+    pub publisher: Box<dyn PublisherTrait>,
+}
+
+impl PublisherEntry {
+    /// Publishes event and accounts the result in metrics and health state
+    pub fn publish(&self, event: &EventInfo) -> Result<(), PublisherError> {
+        let result = self.publisher.publish(event);
+        self.account(&result);
+        result
+    }
+    pub fn publish_system(&self, event: &SystemEvent) -> Result<(), PublisherError> {
+        let result = self.publisher.publish_system(event);
+        self.account(&result);
+        result
+    }
+    /// Updates metrics and publisher's connectivity in health state
+    fn account(&self, result: &Result<(), PublisherError>) {
+        let status = if result.is_ok() { "success" } else { "failure" };
+        metrics().publish.with_label_values(&[&self.name, status]).inc();
+        health().set_publisher_result(&self.name, result.as_ref().err().map(|err| format!("{:?}", err)));
+    }
+}
+
+pub struct PublishersRegistry {
+    entries: Vec<Box<dyn PublisherSettingsTrait + Send>>,
+}
+
+impl Default for PublishersRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PublishersRegistry {
+    pub fn new() -> Self {
+        PublishersRegistry {
+            entries: vec![],
+        }
+    }
+    pub fn register<T: PublisherSettingsTrait + Send + 'static>(&mut self, settings: T) {
+        self.entries.push(Box::new(settings));
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Names of the registered publishers (including disabled ones) in order of registration
+    pub fn names(&self) -> Vec<String> {
+        let mut kinds_counter: HashMap<&'static str, usize> = HashMap::new();
+        self.entries.iter().map(|entry| {
+            let kind_idx = kinds_counter.entry(entry.kind()).or_insert(0);
+            let name = match entry.name() {
+                Some(name) => name.to_string(),
+                None => format!("{}_{}", entry.kind(), kind_idx),
+            };
+            *kind_idx += 1;
+            name
+        }).collect()
+    }
+    /// Constructs every enabled publisher. Publishers which can't be created are reported and ignored
+    pub fn build_all(&self) -> Vec<PublisherEntry> {
+        let mut publishers: Vec<PublisherEntry> = Vec::with_capacity(self.entries.len());
+        for (name, entry) in self.names().into_iter().zip(self.entries.iter()) {
+            if !entry.is_enabled() {
+                println!("Publisher '{}' is disabled. Skipping it", name);
+                continue;
+            }
+            match entry.build() {
+                Ok(publisher) => {
+                    println!("Publisher '{}' has been initialized", name);
+                    publishers.push(PublisherEntry{ name, publisher });
+                },
+                Err(e) => eprintln!("Failed to create publisher '{}': {:?}. Ignoring it", name, e),
+            }
+        }
+        publishers
+    }
+}
@@ -0,0 +1,183 @@
+use crate::publisher::publisher::{PublisherTrait, PublisherError};
+use crate::publisher::registry::PublisherSettingsTrait;
+use crate::events::events::EventInfo;
+use crate::events::system_events::SystemEvent;
+use crate::app::WebhookPublisherSettings;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use ureq::{Agent, AgentBuilder};
+use std::time::Duration;
+
+const DEFAULT_SIGNATURE_HEADER: &str = "X-Signature-SHA256";
+
+/// Publishes events as JSON via HTTP POST request to the custom REST API
+pub struct WebhookPublisher {
+    pub url: String,
+    headers: Vec<(String, String)>,
+    signature_header: String,
+    hmac_secret: Option<String>,
+    agent: Agent,
+}
+
+impl WebhookPublisher {
+    pub fn new(url: String, headers: Vec<(String, String)>, timeout_connect_ms: Option<u64>, timeout_ms: Option<u64>) -> Self {
+        let mut builder = AgentBuilder::new();
+        if let Some(ms) = timeout_connect_ms {
+            builder = builder.timeout_connect(Duration::from_millis(ms));
+        }
+        if let Some(ms) = timeout_ms {
+            builder = builder.timeout(Duration::from_millis(ms));
+        }
+        WebhookPublisher {
+            url,
+            headers,
+            signature_header: DEFAULT_SIGNATURE_HEADER.to_string(),
+            hmac_secret: None,
+            agent: builder.build(),
+        }
+    }
+    /// Enables signing of request body via HMAC-SHA256. Hex digest is placed into the given header (or "X-Signature-SHA256" if header is empty)
+    pub fn set_hmac(&mut self, secret: String, header: String) {
+        self.hmac_secret = Some(secret);
+        if !header.is_empty() {
+            self.signature_header = header;
+        }
+    }
+    /// Sends JSON body with custom headers and signature. Returns HTTP status
+    fn post(&self, body: &str) -> Result<u16, PublisherError> {
+        let mut request = self.agent.post(&self.url).set("Content-Type", "application/json");
+        for (name, value) in self.headers.iter() {
+            request = request.set(name, value);
+        }
+        if let Some(signature) = self.sign(body) {
+            request = request.set(&self.signature_header, &format!("sha256={}", signature));
+        }
+        let response = request.send_string(body)?;
+        Ok(response.status())
+    }
+    fn sign(&self, body: &str) -> Option<String> {
+        let secret = self.hmac_secret.as_ref()?;
+        // HMAC accepts keys of any size, so it is safe to unwrap here
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        Some(digest.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+}
+
+impl PublisherTrait for WebhookPublisher {
+    fn publish(&self, event_info: &EventInfo) -> Result<(), PublisherError> {
+        let event_id = event_info.get_id();
+        let object_id = event_info.get_object_id();
+        println!("Trying to POST event: {}. Object ID: '{}'. URL: '{}'. Time: {}", event_id, object_id, self.url, chrono::Utc::now());
+        let event_json_str = serde_json::to_string(event_info)?;
+        let status = self.post(&event_json_str)?;
+        println!("Success for POST event: '{}'. Object ID: '{}'. Status: {}. Time: {}", event_id, object_id, status, chrono::Utc::now());
+        Ok(())
+    }
+    fn publish_system(&self, event: &SystemEvent) -> Result<(), PublisherError> {
+        let event_json_str = serde_json::to_string(event)?;
+        let status = self.post(&event_json_str)?;
+        println!("Success for POST system event: '{}'. Status: {}. Time: {}", event.get_id(), status, chrono::Utc::now());
+        Ok(())
+    }
+}
+
+impl PublisherSettingsTrait for WebhookPublisherSettings {
+    fn kind(&self) -> &'static str {
+        "webhook"
+    }
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    fn is_enabled(&self) -> bool {
+        self.enable
+    }
+    fn build(&self) -> Result<Box<dyn PublisherTrait>, PublisherError> {
+        let headers: Vec<(String, String)> = self.headers.clone().unwrap_or_default().into_iter().collect();
+        let mut publisher = WebhookPublisher::new(self.url.clone(), headers, self.timeout_connect_ms, self.timeout_ms);
+        if let Some(secret) = self.hmac_secret.clone() {
+            publisher.set_hmac(secret, self.hmac_header.clone().unwrap_or_default());
+        }
+        Ok(Box::new(publisher))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::events::{EventBBox, EventPOI};
+
+    use std::io::Read;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Headers (with lowercase names) and body of the received request
+    type Received = (Vec<(String, String)>, String);
+
+    /// Starts local HTTP server which answers single request with given status. Returns its URL and receiver of the request
+    fn serve_once(status: u16, delay: Duration) -> (String, mpsc::Receiver<Received>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let headers = request.headers().iter().map(|h| (h.field.as_str().as_str().to_lowercase(), h.value.as_str().to_string())).collect();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            tx.send((headers, body)).unwrap();
+            thread::sleep(delay);
+            let _ = request.respond(tiny_http::Response::empty(status));
+        });
+        (format!("http://127.0.0.1:{}/events", port), rx)
+    }
+
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers.iter().find(|(field, _)| field == &name.to_lowercase()).map(|(_, value)| value.as_str())
+    }
+
+    fn event() -> EventInfo {
+        EventInfo::new(1_700_000_000, None, "object".to_string(), 1_700_000_000, 5, EventBBox{x: 1, y: 2, width: 3, height: 4}, EventPOI{x: 2, y: 4}, "accident".to_string(), 0.9, "zone_1".to_string(), None)
+    }
+
+    #[test]
+    fn sign_matches_known_digest() {
+        let mut publisher = WebhookPublisher::new("http://127.0.0.1:1".to_string(), vec![], None, None);
+        assert_eq!(publisher.sign("body"), None);
+        publisher.set_hmac("key".to_string(), String::new());
+        assert_eq!(publisher.sign("The quick brown fox jumps over the lazy dog").unwrap(), "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+    }
+
+    #[test]
+    fn publish_sends_body_headers_and_signature() {
+        let (url, rx) = serve_once(200, Duration::ZERO);
+        let mut publisher = WebhookPublisher::new(url, vec![("Authorization".to_string(), "Bearer token".to_string())], Some(1000), Some(5000));
+        publisher.set_hmac("secret".to_string(), "X-Hub-Signature".to_string());
+        let event = event();
+        publisher.publish(&event).unwrap();
+        let (headers, body) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(body, serde_json::to_string(&event).unwrap());
+        assert_eq!(header(&headers, "Content-Type"), Some("application/json"));
+        assert_eq!(header(&headers, "Authorization"), Some("Bearer token"));
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(body.as_bytes());
+        let expected: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(header(&headers, "X-Hub-Signature"), Some(format!("sha256={}", expected).as_str()));
+        assert_eq!(header(&headers, DEFAULT_SIGNATURE_HEADER), None);
+    }
+
+    #[test]
+    fn publish_fails_on_error_status() {
+        let (url, _rx) = serve_once(500, Duration::ZERO);
+        let publisher = WebhookPublisher::new(url, vec![], Some(1000), Some(5000));
+        assert!(publisher.publish(&event()).is_err());
+    }
+
+    #[test]
+    fn publish_fails_on_timeout() {
+        let (url, _rx) = serve_once(200, Duration::from_secs(2));
+        let publisher = WebhookPublisher::new(url, vec![], Some(1000), Some(200));
+        assert!(publisher.publish(&event()).is_err());
+    }
+}
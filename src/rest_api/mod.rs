@@ -0,0 +1,5 @@
+mod rest_api_error;
+mod rest_api;
+mod monitoring;
+
+pub use self::{rest_api_error::*, rest_api::*, monitoring::*};
@@ -0,0 +1,66 @@
+use crate::rest_api::{error_response, json_response, RestApiError};
+use crate::metrics::metrics;
+use crate::health::health;
+
+use tiny_http::{Server, Request, Response, Header, Method};
+
+use std::io::Cursor;
+use std::thread;
+
+/// Read-only HTTP server for monitoring systems. It does not expose settings, so it could be reachable
+/// by Prometheus without enabling REST API.
+/// Routes:
+/// - GET /metrics (Prometheus text format)
+/// - GET /healthz, /readyz (503 if application is not healthy or not ready)
+pub struct MonitoringServer;
+
+impl MonitoringServer {
+    /// Starts HTTP server in background. Requests are handled one by one
+    pub fn start(host: &str, port: u16) -> Result<(), RestApiError> {
+        let address = format!("{}:{}", host, port);
+        let server = Server::http(&address).map_err(|e| RestApiError::ServerError(format!("{}: {}", address, e)))?;
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle(request);
+            }
+        });
+        println!("Monitoring endpoints are available at http://{}/metrics, /healthz, /readyz", address);
+        Ok(())
+    }
+}
+
+fn handle(request: Request) {
+    let path = request.url().split('?').next().unwrap_or("").trim_end_matches('/').to_string();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let response = match monitoring_response(request.method(), &segments) {
+        Some(response) => response,
+        None => error_response(404, "Not found"),
+    };
+    if let Err(err) = request.respond(response) {
+        eprintln!("Can't send monitoring response: {}", err);
+    }
+}
+
+/// Handles monitoring routes. None if route is not a monitoring one
+pub(crate) fn monitoring_response(method: &Method, segments: &[&str]) -> Option<Response<Cursor<Vec<u8>>>> {
+    match (method, segments) {
+        (Method::Get, ["metrics"]) => {
+            Some(match metrics().encode() {
+                Ok((content_type, body)) => {
+                    let header = Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).expect("Content-Type header should be valid");
+                    Response::from_string(body).with_status_code(200).with_header(header)
+                },
+                Err(err) => error_response(500, &format!("Can't encode metrics: {}", err)),
+            })
+        },
+        (Method::Get, ["healthz"]) => {
+            let report = health().report();
+            Some(json_response(if report.healthy { 200 } else { 503 }, &report))
+        },
+        (Method::Get, ["readyz"]) => {
+            let report = health().report();
+            Some(json_response(if report.ready { 200 } else { 503 }, &report))
+        },
+        _ => None
+    }
+}
@@ -0,0 +1,235 @@
+use crate::rest_api::{monitoring_response, RestApiError};
+use crate::app::{AppError, AppSettings, RuntimeSettings, ZoneSettings, ExclusionSettings};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tiny_http::{Server, Request, Response, Header, Method};
+
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+use std::thread;
+
+// Configuration is small, so there is no reason to accept huge bodies
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+/// Partial update of [detection] section
+#[derive(Deserialize, Debug)]
+pub struct DetectionPatch {
+    pub conf_threshold: Option<f32>,
+    pub nms_threshold: Option<f32>,
+    /// Empty array means every class of the network
+    pub target_classes: Option<Vec<String>>,
+}
+
+/// Partial update of [tracking] section
+#[derive(Deserialize, Debug)]
+pub struct TrackingPatch {
+    pub lifetime_seconds_min: Option<u64>,
+    pub lifetime_seconds_max: Option<u64>,
+    /// Zero disables heartbeats
+    pub heartbeat_seconds: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ErrorMessage {
+    error: String,
+}
+
+/// HTTP API for reading and changing settings of the running application.
+/// Routes:
+/// - GET /api/settings (secrets of publishers are masked)
+/// - PATCH /api/settings/detection
+/// - PATCH /api/settings/tracking
+/// - GET, PUT /api/settings/zones
+/// - GET, PUT, DELETE /api/settings/zones/{id}
+/// - GET, PUT /api/settings/exclusions
+/// - same zones and exclusions routes under /api/settings/inputs/{camera_id} for [[inputs]]
+/// - GET /metrics (Prometheus text format). It is also served by `MonitoringServer`
+/// - GET /healthz, /readyz (503 if application is not healthy or not ready). They are also served by `MonitoringServer`
+pub struct RestApi {
+    runtime_settings: Arc<RuntimeSettings>,
+}
+
+impl RestApi {
+    pub fn new(runtime_settings: Arc<RuntimeSettings>) -> Self {
+        RestApi { runtime_settings }
+    }
+    /// Starts HTTP server in background. Requests are handled one by one
+    pub fn start(self, host: &str, port: u16) -> Result<(), RestApiError> {
+        let address = format!("{}:{}", host, port);
+        let server = Server::http(&address).map_err(|e| RestApiError::ServerError(format!("{}: {}", address, e)))?;
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                self.handle(request);
+            }
+        });
+        println!("REST API is available at http://{}/api", address);
+        Ok(())
+    }
+    fn handle(&self, mut request: Request) {
+        let mut body = String::new();
+        if let Err(err) = request.as_reader().take(MAX_BODY_BYTES).read_to_string(&mut body) {
+            let _ = request.respond(error_response(400, &format!("Can't read request body: {}", err)));
+            return;
+        }
+        let path = request.url().split('?').next().unwrap_or("").trim_end_matches('/').to_string();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let (camera_id, segments) = split_camera_scope(segments);
+        if let Some(camera_id) = camera_id {
+            if !self.runtime_settings.snapshot().inputs.iter().flatten().any(|camera| camera.id == camera_id) {
+                let _ = request.respond(error_response(404, &format!("Camera '{}' not found", camera_id)));
+                return;
+            }
+        }
+        if let Some(response) = monitoring_response(request.method(), &segments) {
+            if let Err(err) = request.respond(response) {
+                eprintln!("Can't send REST API response: {}", err);
+            }
+            return;
+        }
+        let response = match (request.method(), segments.as_slice()) {
+            (Method::Get, ["api", "settings"]) => {
+                json_response(200, &self.runtime_settings.snapshot().redacted())
+            },
+            (Method::Patch, ["api", "settings", "detection"]) => {
+                self.apply_json(&body, |settings, patch: DetectionPatch| {
+                    if let Some(v) = patch.conf_threshold {
+                        settings.detection.conf_threshold = v;
+                    }
+                    if let Some(v) = patch.nms_threshold {
+                        settings.detection.nms_threshold = v;
+                    }
+                    if let Some(v) = patch.target_classes {
+                        settings.detection.target_classes = Some(v);
+                    }
+                    Ok(())
+                }, |settings| json_response(200, &settings.detection))
+            },
+            (Method::Patch, ["api", "settings", "tracking"]) => {
+                self.apply_json(&body, |settings, patch: TrackingPatch| {
+                    if let Some(v) = patch.lifetime_seconds_min {
+                        settings.tracking.lifetime_seconds_min = v;
+                    }
+                    if let Some(v) = patch.lifetime_seconds_max {
+                        settings.tracking.lifetime_seconds_max = v;
+                    }
+                    if let Some(v) = patch.heartbeat_seconds {
+                        settings.tracking.heartbeat_seconds = Some(v);
+                    }
+                    Ok(())
+                }, |settings| json_response(200, &settings.tracking))
+            },
+            (Method::Get, ["api", "settings", "zones"]) => {
+                json_response(200, &zones_of(self.runtime_settings.snapshot(), camera_id))
+            },
+            (Method::Put, ["api", "settings", "zones"]) => {
+                self.apply_json(&body, |settings, zones: Vec<ZoneSettings>| {
+                    *settings.areas_mut(camera_id)?.0 = Some(zones);
+                    Ok(())
+                }, |settings| json_response(200, &zones_of(settings, camera_id)))
+            },
+            (Method::Get, ["api", "settings", "zones", zone_id]) => {
+                match zones_of(self.runtime_settings.snapshot(), camera_id).into_iter().find(|zone| zone.id == *zone_id) {
+                    Some(zone) => json_response(200, &zone),
+                    None => error_response(404, &format!("Zone '{}' not found", zone_id)),
+                }
+            },
+            (Method::Put, ["api", "settings", "zones", zone_id]) => {
+                let zone_id = zone_id.to_string();
+                self.apply_json(&body, |settings, mut zone: ZoneSettings| {
+                    zone.id = zone_id.clone();
+                    let zones = settings.areas_mut(camera_id)?.0.get_or_insert_with(Vec::new);
+                    match zones.iter_mut().find(|z| z.id == zone_id) {
+                        Some(existing) => *existing = zone,
+                        None => zones.push(zone),
+                    }
+                    Ok(())
+                }, |settings| json_response(200, &zones_of(settings, camera_id).into_iter().find(|z| z.id == zone_id)))
+            },
+            (Method::Delete, ["api", "settings", "zones", zone_id]) => {
+                let exists = zones_of(self.runtime_settings.snapshot(), camera_id).iter().any(|zone| zone.id == *zone_id);
+                if !exists {
+                    error_response(404, &format!("Zone '{}' not found", zone_id))
+                } else {
+                    self.apply(|settings| {
+                        if let Some(zones) = settings.areas_mut(camera_id)?.0.as_mut() {
+                            zones.retain(|zone| zone.id != *zone_id);
+                        }
+                        Ok(())
+                    }, |_| Response::from_string("").with_status_code(204))
+                }
+            },
+            (Method::Get, ["api", "settings", "exclusions"]) => {
+                json_response(200, &exclusions_of(self.runtime_settings.snapshot(), camera_id))
+            },
+            (Method::Put, ["api", "settings", "exclusions"]) => {
+                self.apply_json(&body, |settings, exclusions: Vec<ExclusionSettings>| {
+                    *settings.areas_mut(camera_id)?.1 = Some(exclusions);
+                    Ok(())
+                }, |settings| json_response(200, &exclusions_of(settings, camera_id)))
+            },
+            (_, ["api", "settings", ..]) => error_response(405, "Method not allowed"),
+            _ => error_response(404, "Not found"),
+        };
+        if let Err(err) = request.respond(response) {
+            eprintln!("Can't send REST API response: {}", err);
+        }
+    }
+    /// Parses body as JSON and applies it to settings
+    fn apply_json<T, F, R>(&self, body: &str, change: F, on_success: R) -> Response<Cursor<Vec<u8>>>
+    where
+        T: DeserializeOwned,
+        F: FnOnce(&mut AppSettings, T) -> Result<(), AppError>,
+        R: FnOnce(AppSettings) -> Response<Cursor<Vec<u8>>>,
+    {
+        let value: T = match serde_json::from_str(body) {
+            Ok(value) => value,
+            Err(err) => return error_response(400, &format!("Bad JSON: {}", err)),
+        };
+        self.apply(|settings| change(settings, value), on_success)
+    }
+    fn apply<F, R>(&self, change: F, on_success: R) -> Response<Cursor<Vec<u8>>>
+    where
+        F: FnOnce(&mut AppSettings) -> Result<(), AppError>,
+        R: FnOnce(AppSettings) -> Response<Cursor<Vec<u8>>>,
+    {
+        match self.runtime_settings.update(change) {
+            Ok(settings) => on_success(settings),
+            Err(err @ (AppError::IOError(_) | AppError::TOMLSerError(_))) => {
+                error_response(500, &format!("Can't persist settings: {}", err))
+            },
+            Err(err) => error_response(400, &err.to_string()),
+        }
+    }
+}
+
+/// Zones and exclusions of [[inputs]] camera are addressed as /api/settings/inputs/{camera_id}/zones/..., which is rewritten as /api/settings/zones/...
+fn split_camera_scope(segments: Vec<&str>) -> (Option<&str>, Vec<&str>) {
+    if let ["api", "settings", "inputs", camera_id, "zones" | "exclusions", ..] = segments.as_slice() {
+        let mut scoped = vec!["api", "settings"];
+        scoped.extend_from_slice(&segments[4..]);
+        return (Some(*camera_id), scoped);
+    }
+    (None, segments)
+}
+
+fn zones_of(mut settings: AppSettings, camera_id: Option<&str>) -> Vec<ZoneSettings> {
+    settings.areas_mut(camera_id).ok().and_then(|(zones, _)| zones.take()).unwrap_or_default()
+}
+
+fn exclusions_of(mut settings: AppSettings, camera_id: Option<&str>) -> Vec<ExclusionSettings> {
+    settings.areas_mut(camera_id).ok().and_then(|(_, exclusions)| exclusions.take()).unwrap_or_default()
+}
+
+pub(crate) fn json_response<T: Serialize>(status: u16, value: &T) -> Response<Cursor<Vec<u8>>> {
+    match serde_json::to_string(value) {
+        Ok(body) => {
+            let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).expect("Content-Type header should be valid");
+            Response::from_string(body).with_status_code(status).with_header(header)
+        },
+        Err(err) => Response::from_string(format!("Can't serialize response: {}", err)).with_status_code(500)
+    }
+}
+
+pub(crate) fn error_response(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json_response(status, &ErrorMessage { error: message.to_string() })
+}
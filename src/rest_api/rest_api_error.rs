@@ -0,0 +1,14 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum RestApiError {
+    ServerError(String),
+}
+
+impl fmt::Display for RestApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestApiError::ServerError(e) => write!(f, "Can't start REST API server: {}", e),
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use crate::detection::Detections;
+use crate::detection::{Detections, DetectionSources};
 use crate::tracker::TrackerError;
+use crate::video_capture::FrameTime;
 
 use uuid::Uuid;
-use chrono::Utc;
 use mot_rs::mot::IoUTracker;
 
 use std::collections::HashMap;
@@ -15,9 +15,12 @@ use std::fmt;
 pub struct ObjectExtra {
     class_name: String,
     confidence: f32,
-    register_unix_tm: i64,
+    static_seconds: Option<f32>,
+    /// Every image the object has been detected on during its lifetime
+    sources: DetectionSources,
+    register_unix_ms: i64,
     register_relative_second: f32,
-    updated_unix_tm: i64,
+    updated_unix_ms: i64,
     updated_relative_second: f32
 }
 
@@ -28,11 +31,23 @@ impl ObjectExtra {
     pub fn get_confidence(&self) -> f32 {
         self.confidence
     }
+    /// How long the region of the object has been static at the last match
+    pub fn get_static_seconds(&self) -> Option<f32> {
+        self.static_seconds
+    }
+    pub fn get_sources(&self) -> DetectionSources {
+        self.sources
+    }
+    /// Lifetime in seconds measured by the frames' time source
     pub fn get_lifetime(&self) -> i64 {
-        (self.updated_unix_tm - self.register_unix_tm).abs()
+        (self.updated_unix_ms - self.register_unix_ms).abs() / 1000
     }
     pub fn get_register_time(&self) -> i64 {
-        self.register_unix_tm
+        self.register_unix_ms.div_euclid(1000)
+    }
+    /// Offset (in seconds) from the beginning of the stream when object has been registered
+    pub fn get_register_relative_second(&self) -> f32 {
+        self.register_relative_second
     }
 }
 
@@ -54,23 +69,30 @@ impl Tracker {
             objects_extra: HashMap::new(),
         }
     }
-    pub fn match_objects(&mut self, detections: &mut Detections, current_relative_second: f32) -> Result<(), TrackerError> {
+    pub fn match_objects(&mut self, detections: &mut Detections, frame_time: &FrameTime) -> Result<(), TrackerError> {
         self.engine.match_objects(&mut detections.blobs)?;
-        let current_ut = Utc::now().timestamp();
+        let current_ut = frame_time.unix_ms;
+        let current_relative_second = frame_time.media_offset_seconds();
         for (idx, detection) in detections.blobs.iter().enumerate() {
             let object_id = detection.get_id();
             match self.objects_extra.entry(object_id) {
                 Occupied(mut entry) => {
-                    entry.get_mut().updated_unix_tm = current_ut;
+                    entry.get_mut().confidence = detections.confidences[idx];
+                    entry.get_mut().static_seconds = detections.static_seconds[idx];
+                    let sources = entry.get().sources.union(&detections.sources[idx]);
+                    entry.get_mut().sources = sources;
+                    entry.get_mut().updated_unix_ms = current_ut;
                     entry.get_mut().updated_relative_second = current_relative_second;
                 },
                 Vacant(entry) => {
                     let object_extra = ObjectExtra {
                         class_name: detections.class_names[idx].to_owned(),
                         confidence: detections.confidences[idx],
-                        register_unix_tm: current_ut,
+                        static_seconds: detections.static_seconds[idx],
+                        sources: detections.sources[idx],
+                        register_unix_ms: current_ut,
                         register_relative_second: current_relative_second,
-                        updated_unix_tm: current_ut,
+                        updated_unix_ms: current_ut,
                         updated_relative_second: current_relative_second,
                     };
                     entry.insert(object_extra);
@@ -1,8 +1,10 @@
-use serde::Serializer;
+use serde::{Serializer, Deserialize, Deserializer};
 use opencv::{
     core::Mat,
     core::Vector,
-    imgcodecs::imencode
+    imgcodecs::imencode,
+    imgcodecs::imdecode,
+    imgcodecs::IMREAD_COLOR,
 };
 use base64::{
     Engine,
@@ -28,3 +30,22 @@ pub fn mat_as_base64(mat: &Mat) -> Result<String, opencv::Error> {
     // Convert the bytes to a base64 string
     Ok(general_purpose::STANDARD.encode(&buf))
 }
+
+pub fn deserialize_base64_as_mat<'de, D>(deserializer: D) -> Result<Option<Mat>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(base64_string) => {
+            let mat = base64_as_mat(&base64_string).map_err(serde::de::Error::custom)?;
+            Ok(Some(mat))
+        },
+        None => Ok(None)
+    }
+}
+
+pub fn base64_as_mat(base64_string: &str) -> Result<Mat, String> {
+    let bytes = general_purpose::STANDARD.decode(base64_string).map_err(|e| e.to_string())?;
+    let buf: Vector<u8> = Vector::from_slice(&bytes);
+    imdecode(&buf, IMREAD_COLOR).map_err(|e| e.to_string())
+}
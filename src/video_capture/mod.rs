@@ -1,5 +1,7 @@
 // Copy of https://github.com/LdDl/rust-road-traffic/blob/master/src/video_capture/mod.rs
 mod threaded_frame;
 mod video_capture;
+mod time_source;
+mod reconnect;
 
-pub use self::{threaded_frame::*, video_capture::*};
\ No newline at end of file
+pub use self::{threaded_frame::*, video_capture::*, time_source::*, reconnect::*};
\ No newline at end of file
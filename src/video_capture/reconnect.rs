@@ -0,0 +1,73 @@
+use crate::video_capture::{get_video_capture, VideoCaptureError, VideoCaptureInternalError};
+
+use opencv::{
+    prelude::VideoCaptureTraitConst,
+    videoio::VideoCapture,
+};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Granularity of checking for stop request while waiting between attempts
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How to reopen the video source after it has failed
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// None means unlimited number of attempts
+    pub max_retries: Option<u32>,
+    pub backoff_initial: Duration,
+    pub backoff_max: Duration,
+    /// Random deviation of the delay as fraction of it (0..1). Prevents many devices from reconnecting simultaneously
+    pub jitter: f32,
+}
+
+impl ReconnectPolicy {
+    /// Exponential backoff with jitter for the given attempt (starting from 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        let base = self.backoff_initial.saturating_mul(factor).min(self.backoff_max);
+        if self.jitter <= 0.0 {
+            return base;
+        }
+        // Sub-second part of the clock is random enough for spreading reconnects
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let random = (nanos % 10_000) as f32 / 10_000.0;
+        base.mul_f32((1.0 + self.jitter * (2.0 * random - 1.0)).max(0.0))
+    }
+}
+
+/// Opens the video source again waiting between attempts according to the policy.
+/// Returns opened capture and number of attempts it took. Waiting is interrupted as soon as `stop` is set
+pub fn reopen_video_capture(video_src: &str, typ: &str, policy: &ReconnectPolicy, stop: &AtomicBool) -> Result<(VideoCapture, u32), VideoCaptureError> {
+    let mut attempt: u32 = 0;
+    loop {
+        attempt += 1;
+        let delay = policy.delay(attempt);
+        println!("Reconnecting to the video source in {:?} (attempt {})", delay, attempt);
+        let wait_until = Instant::now() + delay;
+        while Instant::now() < wait_until {
+            if stop.load(Ordering::Relaxed) {
+                return Err(VideoCaptureError::from(VideoCaptureInternalError{typ: 3, txt: String::new()}));
+            }
+            thread::sleep(STOP_CHECK_INTERVAL.min(wait_until.saturating_duration_since(Instant::now())));
+        }
+        match get_video_capture(video_src, typ.to_string()) {
+            Ok(capture) => {
+                if capture.is_opened().unwrap_or(false) {
+                    return Ok((capture, attempt));
+                }
+                eprintln!("Video source has not been opened (attempt {})", attempt);
+            },
+            Err(err) => {
+                eprintln!("Can't open video source (attempt {}): {}", attempt, err);
+            }
+        }
+        if let Some(max_retries) = policy.max_retries {
+            if attempt >= max_retries {
+                return Err(VideoCaptureError::from(VideoCaptureInternalError{typ: 2, txt: format!("Gave up after {} attempts", attempt)}));
+            }
+        }
+    }
+}
@@ -1,8 +1,13 @@
 // Copy of https://github.com/LdDl/rust-road-traffic/blob/master/src/video_capture/frame.rs
+use crate::video_capture::FrameTime;
+
 use opencv::core::Mat;
 
 pub struct ThreadedFrame {
     pub frame: Mat,
     pub overall_seconds: f32,
-    pub current_second: f32
+    pub current_second: f32,
+    pub time: FrameTime,
+    /// First frame after the source has been reopened
+    pub reconnected: bool,
 }
@@ -0,0 +1,83 @@
+use chrono::Utc;
+use std::str::FromStr;
+
+/// Clock which is used to measure objects lifetimes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeSource {
+    /// Media timestamps (position in the file). Results do not depend on processing speed
+    Media,
+    /// Wall-clock time of capturing the frame. Suitable for live streams
+    Wallclock,
+}
+
+impl FromStr for TimeSource {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "media" => Ok(TimeSource::Media),
+            "wallclock" => Ok(TimeSource::Wallclock),
+            _ => Err(format!("Bad time source: '{}'. Available: 'media', 'wallclock'", s)),
+        }
+    }
+}
+
+/// Guesses time source by the video source: files use media time, while cameras and network streams use wall-clock
+pub fn detect_time_source(video_src: &str, typ: &str) -> TimeSource {
+    if typ != "rtsp" {
+        return TimeSource::Wallclock;
+    }
+    let src = video_src.to_lowercase();
+    let is_stream = ["rtsp://", "rtsps://", "rtmp://", "http://", "https://", "udp://", "tcp://"].iter().any(|scheme| src.starts_with(scheme));
+    // GStreamer pipeline (e.g. CSI camera)
+    let is_pipeline = src.contains('!');
+    if is_stream || is_pipeline {
+        TimeSource::Wallclock
+    } else {
+        TimeSource::Media
+    }
+}
+
+/// Time of the frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTime {
+    /// Absolute UTC time in milliseconds. For media time source it is start time of processing shifted by media offset
+    pub unix_ms: i64,
+    /// Offset from the beginning of the stream in milliseconds
+    pub media_offset_ms: i64,
+}
+
+impl FrameTime {
+    pub fn unix_seconds(&self) -> i64 {
+        self.unix_ms.div_euclid(1000)
+    }
+    pub fn media_offset_seconds(&self) -> f32 {
+        self.media_offset_ms as f32 / 1000.0
+    }
+}
+
+pub struct Clock {
+    source: TimeSource,
+    started_at_ms: i64,
+}
+
+impl Clock {
+    pub fn new(source: TimeSource) -> Self {
+        Clock {
+            source,
+            started_at_ms: Utc::now().timestamp_millis(),
+        }
+    }
+    pub fn source(&self) -> TimeSource {
+        self.source
+    }
+    pub fn frame_time(&self, media_offset_ms: i64) -> FrameTime {
+        let unix_ms = match self.source {
+            TimeSource::Media => self.started_at_ms + media_offset_ms,
+            TimeSource::Wallclock => Utc::now().timestamp_millis(),
+        };
+        FrameTime {
+            unix_ms,
+            media_offset_ms,
+        }
+    }
+}
@@ -7,11 +7,13 @@ use opencv::{
 };
 
 #[derive(Debug)]
-pub struct VideoCaptureInternalError{typ: i16, pub txt: String}
+pub struct VideoCaptureInternalError{pub typ: i16, pub txt: String}
 impl fmt::Display for VideoCaptureInternalError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.typ {
             1 => write!(f, "Invalid device identifier"),
+            2 => write!(f, "Can't reconnect to the video source: {}", self.txt),
+            3 => write!(f, "Reconnection has been interrupted"),
             _ => write!(f, "Undefined VideoCapture error")
         }
     }
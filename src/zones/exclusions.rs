@@ -0,0 +1,145 @@
+use crate::zones::zones_error;
+
+use opencv::{
+    core::Mat, core::Point2i, core::Scalar, imgproc::line, imgproc::LINE_8
+};
+
+// Distance between hatch lines in pixels
+const HATCH_STEP: i32 = 12;
+
+/// Polygon where detections should never produce events (billboards, parking lots, bus stops and etc.)
+#[derive(Debug, Clone)]
+pub struct ExclusionPolygon {
+    pub id: String,
+    pub color: Scalar,
+    /// If set then detection is excluded also when this fraction of its bbox area lies inside of the polygon
+    pub bbox_fraction: Option<f32>,
+    coordinates: Vec<[f32; 2]>,
+}
+
+impl ExclusionPolygon {
+    pub fn new(id: String, coordinates: &[[i32; 2]], bbox_fraction: Option<f32>, color_rgb: Option<[u16; 3]>) -> Self {
+        let color = match color_rgb {
+            Some(rgb_array) => Scalar::from((rgb_array[2] as f64, rgb_array[1] as f64, rgb_array[0] as f64)),
+            None => Scalar::from((128., 128., 128.))
+        };
+        ExclusionPolygon {
+            id,
+            color,
+            bbox_fraction,
+            coordinates: coordinates.iter().map(|pair| [pair[0] as f32, pair[1] as f32]).collect(),
+        }
+    }
+    /// Ray casting test
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        let n = self.coordinates.len();
+        let mut inside = false;
+        let mut j = n - 1;
+        for (i, pi) in self.coordinates.iter().enumerate() {
+            let pj = self.coordinates[j];
+            if (pi[1] > y) != (pj[1] > y) && x < (pj[0] - pi[0]) * (y - pi[1]) / (pj[1] - pi[1]) + pi[0] {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+    /// Fraction of the bbox area which lies inside of the polygon (0..1)
+    pub fn bbox_overlap_fraction(&self, x: f32, y: f32, width: f32, height: f32) -> f32 {
+        let bbox_area = width * height;
+        if bbox_area <= 0.0 {
+            return 0.0;
+        }
+        let clipped = clip_by_rect(&self.coordinates, x, y, x + width, y + height);
+        (polygon_area(&clipped) / bbox_area).clamp(0.0, 1.0)
+    }
+    /// Checks if detection with given bbox and point of interest should be ignored
+    pub fn excludes(&self, x: f32, y: f32, width: f32, height: f32, poi_x: f32, poi_y: f32) -> bool {
+        if self.contains_point(poi_x, poi_y) {
+            return true;
+        }
+        match self.bbox_fraction {
+            Some(fraction) => self.bbox_overlap_fraction(x, y, width, height) >= fraction,
+            None => false
+        }
+    }
+    /// Draws polygon border and diagonal hatching inside of it
+    pub fn draw(&self, img: &mut Mat) -> Result<(), zones_error::ZonesError> {
+        let n = self.coordinates.len();
+        for i in 0..n {
+            let a = self.coordinates[i];
+            let b = self.coordinates[(i + 1) % n];
+            line(img, Point2i::new(a[0] as i32, a[1] as i32), Point2i::new(b[0] as i32, b[1] as i32), self.color, 2, LINE_8, 0)?;
+        }
+        // Hatch lines are "x - y = c". Find where every line crosses polygon edges and draw inner parts
+        let diagonals: Vec<f32> = self.coordinates.iter().map(|p| p[0] - p[1]).collect();
+        let c_min = diagonals.iter().cloned().fold(f32::INFINITY, f32::min).ceil() as i32;
+        let c_max = diagonals.iter().cloned().fold(f32::NEG_INFINITY, f32::max).floor() as i32;
+        let mut c = c_min - c_min.rem_euclid(HATCH_STEP) + HATCH_STEP;
+        while c < c_max {
+            let mut crossings: Vec<[f32; 2]> = vec![];
+            for i in 0..n {
+                let a = self.coordinates[i];
+                let b = self.coordinates[(i + 1) % n];
+                let fa = a[0] - a[1] - c as f32;
+                let fb = b[0] - b[1] - c as f32;
+                if (fa <= 0.0) != (fb <= 0.0) {
+                    let t = fa / (fa - fb);
+                    crossings.push([a[0] + t * (b[0] - a[0]), a[1] + t * (b[1] - a[1])]);
+                }
+            }
+            crossings.sort_by(|p, q| p[0].total_cmp(&q[0]));
+            for pair in crossings.chunks_exact(2) {
+                line(img, Point2i::new(pair[0][0] as i32, pair[0][1] as i32), Point2i::new(pair[1][0] as i32, pair[1][1] as i32), self.color, 1, LINE_8, 0)?;
+            }
+            c += HATCH_STEP;
+        }
+        Ok(())
+    }
+}
+
+/// Sutherland-Hodgman clipping of (possibly non-convex) polygon by axis-aligned rectangle
+fn clip_by_rect(polygon: &[[f32; 2]], x_min: f32, y_min: f32, x_max: f32, y_max: f32) -> Vec<[f32; 2]> {
+    let mut output: Vec<[f32; 2]> = polygon.to_vec();
+    // Each clip edge is described by axis, bound and whether inner side is greater than bound
+    let edges: [(usize, f32, bool); 4] = [(0, x_min, true), (0, x_max, false), (1, y_min, true), (1, y_max, false)];
+    for (axis, bound, keep_greater) in edges {
+        let input = output;
+        output = Vec::with_capacity(input.len() + 4);
+        if input.is_empty() {
+            break;
+        }
+        let inside = |p: &[f32; 2]| if keep_greater { p[axis] >= bound } else { p[axis] <= bound };
+        for i in 0..input.len() {
+            let current = input[i];
+            let previous = input[(i + input.len() - 1) % input.len()];
+            let intersection = || {
+                let t = (bound - previous[axis]) / (current[axis] - previous[axis]);
+                [previous[0] + t * (current[0] - previous[0]), previous[1] + t * (current[1] - previous[1])]
+            };
+            match (inside(&previous), inside(&current)) {
+                (true, true) => output.push(current),
+                (true, false) => output.push(intersection()),
+                (false, true) => {
+                    output.push(intersection());
+                    output.push(current);
+                },
+                (false, false) => {}
+            }
+        }
+    }
+    output
+}
+
+fn polygon_area(polygon: &[[f32; 2]]) -> f32 {
+    let n = polygon.len();
+    if n < 3 {
+        return 0.0;
+    }
+    let doubled_area: f32 = (0..n).map(|i| {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        a[0] * b[1] - b[0] * a[1]
+    }).sum();
+    doubled_area.abs() / 2.0
+}
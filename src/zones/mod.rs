@@ -1,4 +1,5 @@
 pub mod zones_error;
 pub mod zones;
+pub mod exclusions;
 
-pub use self::{zones_error::*, zones::*};
+pub use self::{zones_error::*, zones::*, exclusions::*};
@@ -1,42 +1,109 @@
 use crate::zones::zones_error;
-use crate::tracker::Tracker;
-use crate::events::{EventInfo, EventBBox, EventPOI};
+use crate::zones::ExclusionPolygon;
+use crate::tracker::{Tracker, ObjectExtra};
+use crate::video_capture::FrameTime;
+use crate::events::{EventInfo, EventBBox, EventPOI, EventType};
+use crate::metrics::metrics;
+use crate::detection::DetectionSources;
 
 use uuid::Uuid;
-use chrono::Utc;
+use mot_rs::utils::{Point, Rect as BBox};
 use opencv::{
-    core::Mat, core::Point2f, core::Point2i, core::Scalar, core::Vector, imgproc::line, imgproc::point_polygon_test, imgproc::LINE_8
+    core::Mat, core::Point2f, core::Point2i, core::Rect, core::Scalar, core::Vector, imgproc::bounding_rect, imgproc::line, imgproc::point_polygon_test, imgproc::LINE_8
 };
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Units of polygon vertices in configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoordinatesType {
+    /// Raw pixels of the source stream
+    Pixels,
+    /// Values in 0..1 relative to frame width and height
+    Normalized,
+}
+
+impl FromStr for CoordinatesType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pixels" => Ok(CoordinatesType::Pixels),
+            "normalized" => Ok(CoordinatesType::Normalized),
+            _ => Err(format!("Bad coordinates type: '{}'. Available: 'pixels', 'normalized'", s)),
+        }
+    }
+}
+
+/// Conditions which detection should satisfy to produce an event in the zone
+#[derive(Debug, Clone)]
+pub struct ZoneRules {
+    /// Empty set means every class is allowed
+    pub target_classes: HashSet<String>,
+    pub min_confidence: f32,
+    pub lifetime_seconds_min: i64,
+    pub lifetime_seconds_max: i64,
+    /// Minimum area of bbox in pixels
+    pub min_bbox_area: f32,
+    /// Period of "event_updated" messages. None means no heartbeats
+    pub heartbeat_seconds: Option<i64>,
+    /// Images the object should have been detected on (e.g. background only for stopped vehicles)
+    pub required_sources: DetectionSources,
+}
+
+impl ZoneRules {
+    pub fn allows_class(&self, classname: &str) -> bool {
+        self.target_classes.is_empty() || self.target_classes.contains(classname)
+    }
+}
+
+/// Last known state of the object which raised the incident
+#[derive(Debug)]
+struct IncidentObject {
+    registered_at: i64,
+    lifetime: i64,
+    bbox: [f32; 4],
+    poi: [f32; 2],
+    classname: String,
+    confidence: f32,
+    static_seconds: Option<f32>,
+    sources: DetectionSources,
+}
+
+#[derive(Debug)]
+struct Incident {
+    id: Uuid,
+    last_event_ut: i64,
+    object: IncidentObject,
+}
 
 #[derive(Debug)]
 pub struct Zone {
     pub id: String,
     pub color: Scalar,
     pixel_coordinates: Vector<Point2f>,
-    segments: [[Point2i; 2]; 4],
-    objects_registered: HashSet<Uuid>
+    segments: Vec<[Point2i; 2]>,
+    /// Active incidents by object identifier
+    incidents: HashMap<Uuid, Incident>,
+    /// Zone's own exclusion polygons
+    exclusions: Vec<ExclusionPolygon>,
+    /// Exclusion polygons shared across all zones. They are not drawn by the zone
+    global_exclusions: Vec<ExclusionPolygon>,
+    pub rules: ZoneRules,
+    camera_id: String,
 }
 
 impl Zone {
-    pub fn new(id: String, coordinates: [[i32; 2]; 4], color_rgb: Option<[u16; 3]>) -> Self {
+    /// Creates zone from polygon with arbitrary number of vertices. Polygon is expected to be validated via `validate_polygon`
+    pub fn new(id: String, coordinates: &[[i32; 2]], color_rgb: Option<[u16; 3]>, rules: ZoneRules) -> Self {
         let pixel_coordinates: Vector<Point2f> = coordinates.iter().map(|pair| {
             Point2f::new(pair[0] as f32, pair[1] as f32)
         }).collect();
-        let mut segments: [[Point2i; 2]; 4] = [[Point2i::new(0, 0), Point2i::new(0, 0)], [Point2i::new(0, 0), Point2i::new(0, 0)], [Point2i::new(0, 0), Point2i::new(0, 0)], [Point2i::new(0, 0), Point2i::new(0, 0)]];
-        for i in 1..coordinates.len() {
-            let prev_pt = Point2i::new(
-                coordinates[i - 1][0],
-                coordinates[i - 1][1],
-            );
-            let current_pt = Point2i::new(
-                coordinates[i][0],
-                coordinates[i][1],
-            );
-            segments[i-1] = [prev_pt, current_pt];
-        }
-        segments[segments.len() - 1] = [Point2i::new(coordinates[coordinates.len()-1][0], coordinates[coordinates.len()-1][1]), Point2i::new(coordinates[0][0], coordinates[0][1])];
+        let segments: Vec<[Point2i; 2]> = (0..coordinates.len()).map(|i| {
+            let current_pt = coordinates[i];
+            let next_pt = coordinates[(i + 1) % coordinates.len()];
+            [Point2i::new(current_pt[0], current_pt[1]), Point2i::new(next_pt[0], next_pt[1])]
+        }).collect();
         let color = match color_rgb {
             Some(rgb_array) => Scalar::from((rgb_array[2] as f64, rgb_array[1] as f64, rgb_array[0] as f64)),
             None => Scalar::from((0., 0., 0.))
@@ -46,75 +113,300 @@ impl Zone {
             color,
             pixel_coordinates,
             segments,
-            objects_registered: HashSet::new()
+            incidents: HashMap::new(),
+            exclusions: vec![],
+            global_exclusions: vec![],
+            rules,
+            camera_id: String::new(),
         }
     }
+    /// Binds zone to the video source. Camera identifier is put into every event of the zone
+    pub fn with_camera_id(mut self, camera_id: String) -> Self {
+        self.camera_id = camera_id;
+        self
+    }
+    pub fn set_exclusions(&mut self, exclusions: Vec<ExclusionPolygon>, global_exclusions: Vec<ExclusionPolygon>) {
+        self.exclusions = exclusions;
+        self.global_exclusions = global_exclusions;
+    }
+    /// Checks if detection falls into any of zone's or global exclusion polygons
+    pub fn is_excluded(&self, x: f32, y: f32, width: f32, height: f32, poi_x: f32, poi_y: f32) -> bool {
+        self.exclusions.iter().chain(self.global_exclusions.iter()).any(|exclusion| {
+            exclusion.excludes(x, y, width, height, poi_x, poi_y)
+        })
+    }
+    /// Smallest upright rectangle which contains the zone
+    pub fn bounding_rect(&self) -> Result<Rect, zones_error::ZonesError> {
+        Ok(bounding_rect(&self.pixel_coordinates)?)
+    }
     pub fn contains_point(&self, x: f32, y: f32) -> Result<bool, zones_error::ZonesError> {
         let ppt = point_polygon_test(&self.pixel_coordinates, Point2f::new(x, y), false)?;
         Ok(ppt > 0.0)
     }
     pub fn draw(&self, img: &mut Mat) -> Result<(), zones_error::ZonesError> {
-        for seg in self.segments {
+        for seg in self.segments.iter() {
             line(img, seg[0], seg[1], self.color, 2, LINE_8, 0)?;
-        } 
+        }
+        for exclusion in self.exclusions.iter() {
+            exclusion.draw(img)?;
+        }
         Ok(())
     }
-    pub fn process_tracker(&mut self, tracker: &mut Tracker, min_lifetime_seconds: i64, max_lifetime_seconds: i64, app_id: Option<String>, frame: Option<&Mat>) -> Result<Vec<EventInfo>, zones_error::ZonesError> {
+    /// Runs incident state machine for every tracked object:
+    /// - "event_started" when object satisfies zone's rules for the first time;
+    /// - "event_updated" every `heartbeat_seconds` while the incident is active;
+    /// - "event_ended" when tracker forgets the object, object leaves the zone (or enters exclusion) or exceeds max lifetime.
+    ///
+    /// Class, confidence, sources, lifetime and size rules are checked only to start an incident: object which misses them on some frames (e.g. confidence drop) keeps its incident
+    pub fn process_tracker(&mut self, tracker: &mut Tracker, frame_time: &FrameTime, app_id: Option<String>, frame: Option<&Mat>) -> Result<Vec<EventInfo>, zones_error::ZonesError> {
         let mut new_events: Vec<EventInfo> = vec![];
-        let current_ut = Utc::now().timestamp();
+        let current_ut = frame_time.unix_seconds();
+        let mut alive: HashSet<Uuid> = HashSet::new();
+        let mut rearm: Vec<Uuid> = vec![];
         for (object_id, object) in tracker.engine.objects.iter() {
-            // Filter objects which disappeared in current time
-            if object.get_no_match_times() > 1 {
-                continue;
-            }
-            let center = object.get_center();
             let object_extra = match tracker.objects_extra.get(object_id) {
                 Some(extra) => extra,
                 None => continue,
             };
-            let object_extra = object_extra;
-            // Filter objects by min lifetime threshold
+            // Object disappeared in current time. Keep its incident until tracker forgets the object
+            if object.get_no_match_times() > 1 {
+                if self.incidents.contains_key(object_id) {
+                    alive.insert(*object_id);
+                }
+                continue;
+            }
             let object_lifetime = object_extra.get_lifetime();
-            if object_lifetime <= min_lifetime_seconds {
+            let center = object.get_center();
+            let bbox = object.get_bbox();
+            // Filter objects by zone's rules. Incident is over once its object has left the zone
+            let accepted = if self.incidents.contains_key(object_id) {
+                self.is_inside(&center, &bbox)?
+            } else {
+                self.satisfies_rules(object_extra, &center, &bbox)?
+            };
+            if !accepted {
                 continue;
             }
-            let contains_object = self.contains_point(center.x, center.y)?;
-            if contains_object {
-                if self.objects_registered.contains(object_id) {
-                    if object_lifetime > max_lifetime_seconds {
-                        tracker.objects_extra.remove(object_id); // Remove object from tracker data to make it appear in next iteration again if object still exist
-                        self.objects_registered.remove(object_id);
+            alive.insert(*object_id);
+            let object_state = IncidentObject {
+                registered_at: object_extra.get_register_time(),
+                lifetime: object_lifetime,
+                bbox: [bbox.x, bbox.y, bbox.width, bbox.height],
+                poi: [center.x, center.y],
+                classname: object_extra.get_classname(),
+                confidence: object_extra.get_confidence(),
+                static_seconds: object_extra.get_static_seconds(),
+                sources: object_extra.get_sources(),
+            };
+            let event_type = match self.incidents.get_mut(object_id) {
+                Some(incident) => {
+                    incident.object = object_state;
+                    if object_lifetime > self.rules.lifetime_seconds_max {
+                        // Incident is over. Object would be registered as new one (with new incident) if it still exists
+                        rearm.push(*object_id);
+                        continue;
+                    }
+                    match self.rules.heartbeat_seconds {
+                        Some(heartbeat_seconds) if current_ut - incident.last_event_ut >= heartbeat_seconds => {
+                            incident.last_event_ut = current_ut;
+                            EventType::EventUpdated
+                        },
+                        _ => continue
                     }
-                    continue;
+                },
+                None => {
+                    self.incidents.insert(*object_id, Incident {
+                        id: Uuid::new_v4(),
+                        last_event_ut: current_ut,
+                        object: object_state,
+                    });
+                    EventType::EventStarted
                 }
-                self.objects_registered.insert(*object_id);
-                // Prepare event_info
-                let bbox = object.get_bbox();
-                let center = object.get_center();
-                let new_event = EventInfo::new(
-                    current_ut,
-                    frame,
-                    object_id.to_string(),
-                    object_extra.get_register_time(),
-                    object_lifetime,
-                    EventBBox{
-                        x: bbox.x.floor() as i32,
-                        y: bbox.y.floor() as i32,
-                        width: bbox.width.floor() as i32,
-                        height: bbox.height.floor() as i32
-                    },
-                    EventPOI{
-                        x: center.x.floor() as i32,
-                        y: center.y.floor() as i32
-                    },
-                    object_extra.get_classname(),
-                    object_extra.get_confidence(),
-                    self.id.clone(),
-                    app_id.clone(),
-                );
-                new_events.push(new_event);
+            };
+            let incident = &self.incidents[object_id];
+            new_events.push(self.prepare_event(incident, object_id, event_type, frame_time, frame, app_id.clone()));
+        }
+        for object_id in rearm.iter() {
+            tracker.objects_extra.remove(object_id); // Remove object from tracker data to make it appear in next iteration again if object still exist
+            alive.remove(object_id);
+        }
+        // Close incidents of objects which have been forgotten by tracker or have been re-armed
+        let finished: Vec<Uuid> = self.incidents.keys().filter(|object_id| !alive.contains(*object_id)).cloned().collect();
+        for object_id in finished {
+            if let Some(incident) = self.incidents.remove(&object_id) {
+                new_events.push(self.prepare_event(&incident, &object_id, EventType::EventEnded, frame_time, frame, app_id.clone()));
             }
         }
         Ok(new_events)
     }
+    /// Checks if object could start an incident in the zone
+    fn satisfies_rules(&self, object_extra: &ObjectExtra, center: &Point, bbox: &BBox) -> Result<bool, zones_error::ZonesError> {
+        if !self.rules.allows_class(&object_extra.get_classname()) || object_extra.get_confidence() < self.rules.min_confidence {
+            return Ok(false);
+        }
+        if !object_extra.get_sources().contains(&self.rules.required_sources) {
+            return Ok(false);
+        }
+        if object_extra.get_lifetime() <= self.rules.lifetime_seconds_min {
+            return Ok(false);
+        }
+        if bbox.width * bbox.height < self.rules.min_bbox_area {
+            return Ok(false);
+        }
+        self.is_inside(center, bbox)
+    }
+    /// Checks if object is inside the zone and outside of its exclusions
+    fn is_inside(&self, center: &Point, bbox: &BBox) -> Result<bool, zones_error::ZonesError> {
+        if !self.contains_point(center.x, center.y)? {
+            return Ok(false);
+        }
+        Ok(!self.is_excluded(bbox.x, bbox.y, bbox.width, bbox.height, center.x, center.y))
+    }
+    /// Takes over active incidents of the previous version of the zone (e.g. after configuration has been changed)
+    pub fn inherit_incidents(&mut self, previous: &mut Zone) {
+        self.incidents = std::mem::take(&mut previous.incidents);
+    }
+    /// Closes every active incident. Should be called when zone is removed
+    pub fn close_incidents(&mut self, frame_time: &FrameTime, app_id: Option<String>, frame: Option<&Mat>) -> Vec<EventInfo> {
+        let incidents = std::mem::take(&mut self.incidents);
+        incidents.iter().map(|(object_id, incident)| {
+            self.prepare_event(incident, object_id, EventType::EventEnded, frame_time, frame, app_id.clone())
+        }).collect()
+    }
+    fn prepare_event(&self, incident: &Incident, object_id: &Uuid, event_type: EventType, frame_time: &FrameTime, frame: Option<&Mat>, app_id: Option<String>) -> EventInfo {
+        let object = &incident.object;
+        metrics().events.with_label_values(&[&self.camera_id, &self.id, &object.classname, event_type.as_str()]).inc();
+        EventInfo::new(
+            frame_time.unix_seconds(),
+            frame,
+            object_id.to_string(),
+            object.registered_at,
+            object.lifetime,
+            EventBBox{
+                x: object.bbox[0].floor() as i32,
+                y: object.bbox[1].floor() as i32,
+                width: object.bbox[2].floor() as i32,
+                height: object.bbox[3].floor() as i32
+            },
+            EventPOI{
+                x: object.poi[0].floor() as i32,
+                y: object.poi[1].floor() as i32
+            },
+            object.classname.clone(),
+            object.confidence,
+            self.id.clone(),
+            app_id,
+        ).with_incident(event_type, incident.id).with_media_offset(frame_time.media_offset_ms).with_camera_id(self.camera_id.clone()).with_static_seconds(object.static_seconds).with_sources(object.sources)
+    }
+}
+
+/// Converts polygon vertices into pixels of the frame with given size. Checks that every vertex lies inside of the frame:
+/// pixels should be in [0; width)x[0; height), normalized values in [0; 1]x[0; 1] are mapped onto the first and the last pixels
+pub fn resolve_polygon(coordinates: &[[f32; 2]], coordinates_type: CoordinatesType, width: f32, height: f32) -> Result<Vec<[i32; 2]>, zones_error::ZonesError> {
+    coordinates.iter().map(|pair| {
+        let (x, y) = match coordinates_type {
+            CoordinatesType::Pixels => (pair[0].round(), pair[1].round()),
+            CoordinatesType::Normalized => {
+                if pair[0] < 0.0 || pair[0] > 1.0 || pair[1] < 0.0 || pair[1] > 1.0 {
+                    return Err(zones_error::ZonesError::InvalidGeometry(format!("Vertex {:?} is out of bounds [0; 1]x[0; 1] (normalized coordinates)", pair)));
+                }
+                ((pair[0] * (width - 1.0)).round(), (pair[1] * (height - 1.0)).round())
+            },
+        };
+        if x < 0.0 || x >= width || y < 0.0 || y >= height {
+            return Err(zones_error::ZonesError::InvalidGeometry(format!("Vertex {:?} is out of bounds [0; {})x[0; {}) ({:?} coordinates, frame is {}x{})", pair, width, height, coordinates_type, width, height)));
+        }
+        Ok([x as i32, y as i32])
+    }).collect()
+}
+
+/// Checks that polygon has at least 3 vertices, is not degenerate (no repeated vertices, no zero-width spikes, non-zero area) and has no self-intersections.
+/// Vertex in the middle of the straight edge (e.g. when tracing road edge) is allowed
+pub fn validate_polygon(coordinates: &[[i32; 2]]) -> Result<(), zones_error::ZonesError> {
+    let n = coordinates.len();
+    if n < 3 {
+        return Err(zones_error::ZonesError::InvalidGeometry(format!("Polygon should have at least 3 vertices, got {}", n)));
+    }
+    for i in 0..n {
+        if coordinates[i] == coordinates[(i + 1) % n] {
+            return Err(zones_error::ZonesError::InvalidGeometry(format!("Polygon has repeated vertex {:?} at position {}", coordinates[i], i)));
+        }
+    }
+    // Next edge doubling back along the previous one forms zero-width spike
+    for i in 0..n {
+        let (prev, current, next) = (coordinates[(i + n - 1) % n], coordinates[i], coordinates[(i + 1) % n]);
+        let dot = (current[0] as i64 - prev[0] as i64) * (next[0] as i64 - current[0] as i64) + (current[1] as i64 - prev[1] as i64) * (next[1] as i64 - current[1] as i64);
+        if orientation(prev, current, next) == 0 && dot < 0 {
+            return Err(zones_error::ZonesError::InvalidGeometry(format!("Polygon has zero-width spike at vertex {:?} (position {})", current, i)));
+        }
+    }
+    // Shoelace formula (doubled area)
+    let doubled_area: i64 = (0..n).map(|i| {
+        let a = coordinates[i];
+        let b = coordinates[(i + 1) % n];
+        a[0] as i64 * b[1] as i64 - b[0] as i64 * a[1] as i64
+    }).sum();
+    if doubled_area == 0 {
+        return Err(zones_error::ZonesError::InvalidGeometry("Polygon has zero area".to_string()));
+    }
+    // Every pair of non-adjacent edges should not intersect
+    for i in 0..n {
+        let (a1, a2) = (coordinates[i], coordinates[(i + 1) % n]);
+        for j in (i + 2)..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (b1, b2) = (coordinates[j], coordinates[(j + 1) % n]);
+            if segments_intersect(a1, a2, b1, b2) {
+                return Err(zones_error::ZonesError::InvalidGeometry(format!("Polygon is self-intersecting: edge {} ({:?} -> {:?}) crosses edge {} ({:?} -> {:?})", i, a1, a2, j, b1, b2)));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn orientation(p: [i32; 2], q: [i32; 2], r: [i32; 2]) -> i64 {
+    let value = (q[0] as i64 - p[0] as i64) * (r[1] as i64 - p[1] as i64) - (q[1] as i64 - p[1] as i64) * (r[0] as i64 - p[0] as i64);
+    value.signum()
+}
+
+// Checks if point r lies on segment [p, q] given that p, q, r are collinear
+fn on_segment(p: [i32; 2], q: [i32; 2], r: [i32; 2]) -> bool {
+    r[0] >= p[0].min(q[0]) && r[0] <= p[0].max(q[0]) && r[1] >= p[1].min(q[1]) && r[1] <= p[1].max(q[1])
+}
+
+fn segments_intersect(a1: [i32; 2], a2: [i32; 2], b1: [i32; 2], b2: [i32; 2]) -> bool {
+    let o1 = orientation(a1, a2, b1);
+    let o2 = orientation(a1, a2, b2);
+    let o3 = orientation(b1, b2, a1);
+    let o4 = orientation(b1, b2, a2);
+    if o1 != o2 && o3 != o4 {
+        return true;
+    }
+    (o1 == 0 && on_segment(a1, a2, b1)) || (o2 == 0 && on_segment(a1, a2, b2)) || (o3 == 0 && on_segment(b1, b2, a1)) || (o4 == 0 && on_segment(b1, b2, a2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_polygon_accepts_midpoint_on_straight_edge() {
+        assert!(validate_polygon(&[[0, 0], [50, 0], [100, 0], [100, 100], [0, 100]]).is_ok());
+    }
+
+    #[test]
+    fn validate_polygon_rejects_spike() {
+        assert!(validate_polygon(&[[0, 0], [100, 0], [50, 0], [50, 100]]).is_err());
+        assert!(validate_polygon(&[[0, 0], [100, 0], [100, 100], [100, 50], [0, 100]]).is_err());
+    }
+
+    #[test]
+    fn validate_polygon_rejects_degenerate_shapes() {
+        assert!(validate_polygon(&[[0, 0], [100, 0]]).is_err());
+        assert!(validate_polygon(&[[0, 0], [0, 0], [100, 0], [100, 100]]).is_err());
+        assert!(validate_polygon(&[[0, 0], [50, 0], [100, 0]]).is_err());
+        // Bow tie
+        assert!(validate_polygon(&[[0, 0], [100, 100], [100, 0], [0, 100]]).is_err());
+    }
 }
@@ -1,6 +1,18 @@
+use std::fmt;
+
 #[derive(Debug)]
 pub enum ZonesError {
     OpenCVError(opencv::Error),
+    InvalidGeometry(String),
+}
+
+impl fmt::Display for ZonesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZonesError::OpenCVError(e) => write!(f, "{}", e),
+            ZonesError::InvalidGeometry(e) => write!(f, "Invalid geometry: {}", e),
+        }
+    }
 }
 
 impl From<opencv::Error> for ZonesError {
@@ -0,0 +1,108 @@
+use road_anomaly_detection::detection::{process_yolo_detections, ClassFilters, DetectionSources, RawDetections};
+use road_anomaly_detection::detector::{Detector, MockDetection, MockDetector};
+use road_anomaly_detection::events::{EventInfo, EventType};
+use road_anomaly_detection::tracker::Tracker;
+use road_anomaly_detection::video_capture::FrameTime;
+use road_anomaly_detection::zones::{Zone, ZoneRules};
+
+use opencv::core::Mat;
+
+use std::collections::{HashMap, HashSet};
+
+const FPS: f32 = 10.0;
+/// Frames the scripted object is seen on
+const VISIBLE_FRAMES: u64 = 30;
+/// Enough empty frames for tracker to forget the object
+const TOTAL_FRAMES: u64 = 60;
+/// Zone is the square [0; ZONE_SIZE]x[0; ZONE_SIZE]
+const ZONE_SIZE: i32 = 400;
+
+fn zone_rules() -> ZoneRules {
+    ZoneRules {
+        target_classes: HashSet::new(),
+        min_confidence: 0.5,
+        lifetime_seconds_min: 1,
+        lifetime_seconds_max: 60,
+        min_bbox_area: 0.0,
+        heartbeat_seconds: None,
+        required_sources: DetectionSources::background(),
+    }
+}
+
+/// Runs scripted detections through tracker and zone. Returns events with indices of frames they have been emitted on
+fn run(detections: Vec<MockDetection>, total_frames: u64) -> Vec<(u64, EventInfo)> {
+    let net_classes = vec!["accident".to_string()];
+    let target_classes = HashSet::new();
+    let class_filters = ClassFilters::new(0.5, HashMap::new());
+    let mut detector = MockDetector::new(detections);
+    let mut tracker = Tracker::new(5, 0.3);
+    let mut zone = Zone::new("zone_1".to_string(), &[[0, 0], [ZONE_SIZE, 0], [ZONE_SIZE, ZONE_SIZE], [0, ZONE_SIZE]], None, zone_rules());
+    let image = Mat::default();
+    let mut events = vec![];
+    for frame_index in 0..total_frames {
+        let frame_time = FrameTime {
+            unix_ms: 1_700_000_000_000 + (frame_index as f32 * 1000.0 / FPS) as i64,
+            media_offset_ms: (frame_index as f32 * 1000.0 / FPS) as i64,
+        };
+        let (bboxes, classes_ids, confidences) = detector.detect(&image, frame_index, class_filters.min_conf_threshold(), 0.4).unwrap();
+        let raw = RawDetections::new(bboxes, classes_ids, confidences, DetectionSources::background());
+        let mut detections = process_yolo_detections(&raw, &net_classes, &target_classes, &class_filters, 1.0 / FPS, 1.0, 1.0);
+        tracker.match_objects(&mut detections, &frame_time).unwrap();
+        for event in zone.process_tracker(&mut tracker, &frame_time, None, None).unwrap() {
+            events.push((frame_index, event));
+        }
+    }
+    events
+}
+
+fn stationary(bbox: [i32; 4], confidence: f32) -> Vec<MockDetection> {
+    (0..VISIBLE_FRAMES).map(|frame| MockDetection { frame, bbox, class_id: 0, confidence }).collect()
+}
+
+#[test]
+fn stationary_object_in_zone_starts_and_ends_incident() {
+    let events = run(stationary([100, 100, 50, 50], 0.9), TOTAL_FRAMES);
+    let types: Vec<EventType> = events.iter().map(|(_, event)| event.get_event_type()).collect();
+    assert_eq!(types, vec![EventType::EventStarted, EventType::EventEnded]);
+    let (started_at, started) = &events[0];
+    let (ended_at, ended) = &events[1];
+    // Incident starts once object has lived longer than lifetime_seconds_min and ends after object has disappeared
+    assert!(*started_at >= 2 * FPS as u64 && *started_at < VISIBLE_FRAMES, "started on frame {}", started_at);
+    assert!(*ended_at >= VISIBLE_FRAMES, "ended on frame {}", ended_at);
+    assert_eq!(started.get_incident_id(), ended.get_incident_id());
+    assert_eq!(started.get_object_id(), ended.get_object_id());
+    assert_eq!(started.get_zone_id(), "zone_1");
+    assert_eq!(started.get_classname(), "accident");
+}
+
+#[test]
+fn object_outside_zone_produces_no_events() {
+    let events = run(stationary([500, 500, 50, 50], 0.9), TOTAL_FRAMES);
+    assert!(events.is_empty(), "unexpected events: {:?}", events);
+}
+
+#[test]
+fn object_below_confidence_threshold_produces_no_events() {
+    let events = run(stationary([100, 100, 50, 50], 0.3), TOTAL_FRAMES);
+    assert!(events.is_empty(), "unexpected events: {:?}", events);
+}
+
+#[test]
+fn object_leaving_zone_ends_incident_before_it_is_forgotten() {
+    // Object stops for 2.5 seconds, then drives to the right (5 pixels per frame) and leaves the zone while it is still tracked
+    let (stop_frames, last_frame) = (25, 100);
+    let detections: Vec<MockDetection> = (0..last_frame).map(|frame| {
+        let x = 100 + 5 * (frame.saturating_sub(stop_frames) as i32);
+        MockDetection { frame, bbox: [x, 100, 50, 50], class_id: 0, confidence: 0.9 }
+    }).collect();
+    // Center of the box crosses the border of the zone on this frame
+    let leaving_frame = stop_frames + ((ZONE_SIZE - 100 - 25) / 5) as u64;
+    let events = run(detections, last_frame + 30);
+    let types: Vec<EventType> = events.iter().map(|(_, event)| event.get_event_type()).collect();
+    assert_eq!(types, vec![EventType::EventStarted, EventType::EventEnded]);
+    let (started_at, started) = &events[0];
+    let (ended_at, ended) = &events[1];
+    assert!(*started_at < stop_frames, "started on frame {}", started_at);
+    assert!(*ended_at >= leaving_frame - 5 && *ended_at < last_frame, "ended on frame {}, object leaves zone on frame {} and disappears on frame {}", ended_at, leaving_frame, last_frame);
+    assert_eq!(started.get_incident_id(), ended.get_incident_id());
+}